@@ -1,148 +1,13 @@
 use proc_macro2::{Ident, TokenStream};
-use quote::{quote, ToTokens};
-use syn::{spanned::Spanned, Error, FnArg, ImplItem, Item, Type, TypePath};
+use quote::{format_ident, quote, ToTokens};
+use syn::{
+    spanned::Spanned, Error, FnArg, ImplItem, ImplItemMethod, Item, ItemImpl, Type, TypePath,
+};
 
 pub fn expand_macro(tokens: TokenStream) -> syn::Result<TokenStream> {
     let item = syn::parse2::<Item>(tokens)?;
     match item {
-        Item::Impl(imp) => {
-            let impl_token = imp.impl_token;
-            let trai = imp.trait_.clone();
-            let (_, trai, _) = trai.ok_or_else(|| Error::new_spanned(impl_token, "Must be a DurableObject trait impl"))?;
-            if !trai.segments.last().map(|x| x.ident == "DurableObject").unwrap_or(false) {
-                return Err(Error::new(trai.span(), "Must be a DurableObject trait impl"))
-            }
-
-            let pound = syn::Token![#](imp.span()).to_token_stream();
-            let wasm_bindgen_attr = quote! {#pound[wasm_bindgen::prelude::wasm_bindgen]};
-
-
-            let struct_name = imp.self_ty;
-            let items = imp.items;
-            let mut tokenized = vec![];
-            let mut has_alarm = false;
-
-            for item in items {
-                let impl_method = match item {
-                    ImplItem::Method(m) => m,
-                    _ => return Err(Error::new_spanned(item, "Impl block must only contain methods"))
-                };
-
-                let tokens = match impl_method.sig.ident.to_string().as_str() {
-                    "new" => {
-                        let mut method = impl_method.clone();
-                        method.sig.ident = Ident::new("_new", method.sig.ident.span());
-
-                        // modify the `state` argument so it is type ObjectState
-                        let arg_tokens = method.sig.inputs.first_mut().expect("DurableObject `new` method must have 2 arguments: state and env").into_token_stream();                        
-                        match syn::parse2::<FnArg>(arg_tokens)? {
-                            FnArg::Typed(pat) => {
-                                let path = syn::parse2::<TypePath>(quote!{worker_sys::durable_object::ObjectState})?;
-                                let mut updated_pat = pat;
-                                updated_pat.ty = Box::new(Type::Path(path));
-
-                                let state_arg = FnArg::Typed(updated_pat);
-                                let env_arg = method.sig.inputs.pop().expect("DurableObject `new` method expects a second argument: env");
-                                method.sig.inputs.clear();
-                                method.sig.inputs.insert(0, state_arg);
-                                method.sig.inputs.insert(1, env_arg.into_value())
-                            },
-                            _ => return Err(Error::new(method.sig.inputs.span(), "DurableObject `new` method expects `state: State` as first argument."))
-                        }
-
-                        // prepend the function block's statements to convert the ObjectState to State type
-                        let mut prepended = vec![syn::parse_quote! {
-                            let state = ::worker::durable::State::from(state);
-                        }];
-                        prepended.extend(method.block.stmts);
-                        method.block.stmts = prepended;
-
-                        quote! {
-                            #pound[wasm_bindgen::prelude::wasm_bindgen(constructor)]
-                            pub #method
-                        }
-                    },
-                    "fetch" => {
-                        let mut method = impl_method.clone();
-                        method.sig.ident = Ident::new("_fetch_raw", method.sig.ident.span());
-                        quote! {
-                            #pound[wasm_bindgen::prelude::wasm_bindgen(js_name = fetch)]
-                            pub fn _fetch(&mut self, req: worker_sys::Request) -> js_sys::Promise {
-                                // SAFETY:
-                                // On the surface, this is unsound because the Durable Object could be dropped
-                                // while JavaScript still has possession of the future. However,
-                                // we know something that Rust doesn't: that the Durable Object will never be destroyed
-                                // while there is still a running promise inside of it, therefore we can let a reference
-                                // to the durable object escape into a static-lifetime future.
-                                let static_self: &'static mut Self = unsafe {&mut *(self as *mut _)};
-
-                                wasm_bindgen_futures::future_to_promise(async move {
-                                    static_self._fetch_raw(req.into()).await.map(worker_sys::Response::from).map(wasm_bindgen::JsValue::from)
-                                        .map_err(wasm_bindgen::JsValue::from)
-                                })
-                            }
-
-                            #method
-                        }
-                    },
-                    "alarm" => {
-                        has_alarm = true;
-
-                        let mut method = impl_method.clone();
-                        method.sig.ident = Ident::new("_alarm_raw", method.sig.ident.span());
-                        quote! {
-                            #pound[wasm_bindgen::prelude::wasm_bindgen(js_name = alarm)]
-                            pub fn _alarm(&mut self) -> js_sys::Promise {
-                                // SAFETY:
-                                // On the surface, this is unsound because the Durable Object could be dropped
-                                // while JavaScript still has possession of the future. However,
-                                // we know something that Rust doesn't: that the Durable Object will never be destroyed
-                                // while there is still a running promise inside of it, therefore we can let a reference
-                                // to the durable object escape into a static-lifetime future.
-                                let static_self: &'static mut Self = unsafe {&mut *(self as *mut _)};
-
-                                wasm_bindgen_futures::future_to_promise(async move {
-                                    static_self._alarm_raw().await.map(worker_sys::Response::from).map(wasm_bindgen::JsValue::from)
-                                        .map_err(wasm_bindgen::JsValue::from)
-                                })
-                            }
-
-                            #method
-                        }
-                    }
-                    _ => panic!()
-                };
-                tokenized.push(tokens);
-            }
-
-            let alarm_tokens = has_alarm.then(|| quote! {
-                async fn alarm(&mut self) -> ::worker::Result<worker::Response> {
-                    self._alarm_raw().await
-                }
-            });
-            Ok(quote! {
-                #wasm_bindgen_attr
-                impl #struct_name {
-                    #(#tokenized)*
-                }
-
-                #pound[async_trait::async_trait(?Send)]
-                impl ::worker::durable::DurableObject for #struct_name {
-                    fn new(state: ::worker::durable::State, env: ::worker::Env) -> Self {
-                        Self::_new(state._inner(), env)
-                    }
-
-                    async fn fetch(&mut self, req: ::worker::Request) -> ::worker::Result<worker::Response> {
-                        self._fetch_raw(req).await
-                    }
-
-                    #alarm_tokens
-                }
-
-                trait __Need_Durable_Object_Trait_Impl_With_durable_object_Attribute { const MACROED: bool = true; }
-                impl __Need_Durable_Object_Trait_Impl_With_durable_object_Attribute for #struct_name {}
-            })
-        },
+        Item::Impl(imp) => expand_impl(imp),
         Item::Struct(struc) => {
             let tokens = struc.to_token_stream();
             let pound = syn::Token![#](struc.span()).to_token_stream();
@@ -153,7 +18,305 @@ pub fn expand_macro(tokens: TokenStream) -> syn::Result<TokenStream> {
 
                 const _: bool = <#struct_name as __Need_Durable_Object_Trait_Impl_With_durable_object_Attribute>::MACROED;
             })
+        }
+        _ => Err(Error::new(
+            item.span(),
+            "Durable Object macro can only be applied to structs and their impl of DurableObject trait",
+        )),
+    }
+}
+
+/// A method of the `DurableObject` trait which the runtime calls on the exported class. Supporting
+/// a new lifecycle hook only takes describing it here.
+struct Hook {
+    /// The name of the method on the JS class.
+    js_name: &'static str,
+    /// The arguments the runtime passes to the JS method.
+    js_inputs: TokenStream,
+    /// The arguments of the trait method.
+    inputs: TokenStream,
+    /// The names of the trait method's arguments.
+    args: TokenStream,
+    /// Converts the runtime's arguments into the trait method's.
+    from_js: TokenStream,
+    /// The return type of the trait method.
+    output: TokenStream,
+    /// Converts the trait method's successful result into a `JsValue`.
+    into_js: TokenStream,
+}
+
+fn hook(name: &str) -> Option<Hook> {
+    let hook = match name {
+        "fetch" => Hook {
+            js_name: "fetch",
+            js_inputs: quote! { req: worker_sys::Request },
+            inputs: quote! { req: ::worker::Request },
+            args: quote! { req },
+            from_js: quote! { req.into() },
+            output: quote! { ::worker::Result<::worker::Response> },
+            into_js: quote! { |resp| worker_sys::Response::from(resp).into() },
+        },
+        "alarm" => Hook {
+            js_name: "alarm",
+            js_inputs: quote! { info: wasm_bindgen::JsValue },
+            inputs: quote! { info: ::worker::durable::AlarmInvocationInfo },
+            args: quote! { info },
+            from_js: quote! { ::std::convert::TryFrom::try_from(info)? },
+            output: quote! { ::worker::Result<::worker::Response> },
+            into_js: quote! { |resp| worker_sys::Response::from(resp).into() },
+        },
+        "websocket_message" => Hook {
+            js_name: "webSocketMessage",
+            js_inputs: quote! { ws: worker_sys::WebSocket, message: wasm_bindgen::JsValue },
+            inputs: quote! {
+                ws: ::worker::WebSocket,
+                message: ::worker::durable::WebSocketIncomingMessage
+            },
+            args: quote! { ws, message },
+            from_js: quote! { ws.into(), message.into() },
+            output: quote! { ::worker::Result<()> },
+            into_js: quote! { |_| wasm_bindgen::JsValue::UNDEFINED },
         },
-        _ => Err(Error::new(item.span(), "Durable Object macro can only be applied to structs and their impl of DurableObject trait"))
+        "websocket_close" => Hook {
+            js_name: "webSocketClose",
+            js_inputs: quote! {
+                ws: worker_sys::WebSocket,
+                code: u16,
+                reason: String,
+                was_clean: bool
+            },
+            inputs: quote! {
+                ws: ::worker::WebSocket,
+                code: u16,
+                reason: String,
+                was_clean: bool
+            },
+            args: quote! { ws, code, reason, was_clean },
+            from_js: quote! { ws.into(), code, reason, was_clean },
+            output: quote! { ::worker::Result<()> },
+            into_js: quote! { |_| wasm_bindgen::JsValue::UNDEFINED },
+        },
+        "websocket_error" => Hook {
+            js_name: "webSocketError",
+            js_inputs: quote! { ws: worker_sys::WebSocket, error: wasm_bindgen::JsValue },
+            inputs: quote! { ws: ::worker::WebSocket, error: ::worker::Error },
+            args: quote! { ws, error },
+            from_js: quote! { ws.into(), error.into() },
+            output: quote! { ::worker::Result<()> },
+            into_js: quote! { |_| wasm_bindgen::JsValue::UNDEFINED },
+        },
+        _ => return None,
+    };
+
+    Some(hook)
+}
+
+fn expand_impl(imp: ItemImpl) -> syn::Result<TokenStream> {
+    let (_, trai, _) = imp
+        .trait_
+        .as_ref()
+        .ok_or_else(|| Error::new_spanned(imp.impl_token, "Must be a DurableObject trait impl"))?;
+    if !trai
+        .segments
+        .last()
+        .map(|x| x.ident == "DurableObject")
+        .unwrap_or(false)
+    {
+        return Err(Error::new(
+            trai.span(),
+            "Must be a DurableObject trait impl",
+        ));
+    }
+    let trait_span = trai.span();
+
+    let pound = syn::Token![#](imp.span()).to_token_stream();
+    let struct_name = imp.self_ty;
+
+    // methods exported to the runtime on the JS class
+    let mut exports = vec![];
+    // the user's hook methods, renamed, and any other items of the impl block
+    let mut items = vec![];
+    // the `DurableObject` trait methods, forwarding to the user's hook methods
+    let mut trait_methods = vec![];
+    let mut has_new = false;
+    let mut has_fetch = false;
+    let mut has_alarm = false;
+
+    for item in imp.items {
+        let mut method = match item {
+            ImplItem::Method(method) => method,
+            ImplItem::Type(ty) => {
+                return Err(Error::new_spanned(
+                    ty,
+                    "associated types aren't supported in a Durable Object impl",
+                ))
+            }
+            item => {
+                items.push(item.into_token_stream());
+                continue;
+            }
+        };
+
+        let name = method.sig.ident.to_string();
+        if name == "new" {
+            has_new = true;
+            exports.push(expand_new(method)?);
+            continue;
+        }
+
+        let hook = match hook(&name) {
+            Some(hook) => hook,
+            None => {
+                items.push(method.into_token_stream());
+                continue;
+            }
+        };
+        has_fetch |= name == "fetch";
+        has_alarm |= name == "alarm";
+
+        let ident = method.sig.ident.clone();
+        let raw_ident = format_ident!("_{}_raw", ident);
+        exports.push(expand_export(
+            &pound,
+            &ident,
+            &hook,
+            quote! { Self::#raw_ident },
+        ));
+
+        let Hook {
+            inputs,
+            args,
+            output,
+            ..
+        } = hook;
+        trait_methods.push(quote! {
+            async fn #ident(&mut self, #inputs) -> #output {
+                self.#raw_ident(#args).await
+            }
+        });
+
+        method.sig.ident = raw_ident;
+        items.push(method.into_token_stream());
+    }
+
+    for (present, name) in [(has_new, "new"), (has_fetch, "fetch")] {
+        if !present {
+            return Err(Error::new(
+                trait_span,
+                format!("DurableObject impl must define a `{name}` method"),
+            ));
+        }
+    }
+
+    // the runtime has to be able to reach the trait's default `alarm`, which fails the alarm with
+    // an error naming the missing handler instead of the alarm silently never running
+    if !has_alarm {
+        let ident = Ident::new("alarm", trait_span);
+        let hook = hook("alarm").expect("alarm is a hook");
+        exports.push(expand_export(
+            &pound,
+            &ident,
+            &hook,
+            quote! { <Self as ::worker::durable::DurableObject>::alarm },
+        ));
+    }
+
+    Ok(quote! {
+        #pound[wasm_bindgen::prelude::wasm_bindgen]
+        impl #struct_name {
+            #(#exports)*
+        }
+
+        impl #struct_name {
+            #(#items)*
+        }
+
+        #pound[async_trait::async_trait(?Send)]
+        impl ::worker::durable::DurableObject for #struct_name {
+            fn new(state: ::worker::durable::State, env: ::worker::Env) -> Self {
+                Self::_new(state._inner(), env)
+            }
+
+            #(#trait_methods)*
+        }
+
+        trait __Need_Durable_Object_Trait_Impl_With_durable_object_Attribute { const MACROED: bool = true; }
+        impl __Need_Durable_Object_Trait_Impl_With_durable_object_Attribute for #struct_name {}
+    })
+}
+
+/// The method exported on the JS class for a hook, which converts the runtime's arguments and
+/// calls `method` with them.
+fn expand_export(
+    pound: &TokenStream,
+    ident: &Ident,
+    hook: &Hook,
+    method: TokenStream,
+) -> TokenStream {
+    let Hook {
+        js_name,
+        js_inputs,
+        from_js,
+        into_js,
+        ..
+    } = hook;
+    let export_ident = format_ident!("_{}", ident);
+    let js_name = Ident::new(js_name, ident.span());
+
+    quote! {
+        #pound[wasm_bindgen::prelude::wasm_bindgen(js_name = #js_name)]
+        pub fn #export_ident(&mut self, #js_inputs) -> js_sys::Promise {
+            // SAFETY:
+            // On the surface, this is unsound because the Durable Object could be dropped
+            // while JavaScript still has possession of the future. However,
+            // we know something that Rust doesn't: that the Durable Object will never be destroyed
+            // while there is still a running promise inside of it, therefore we can let a reference
+            // to the durable object escape into a static-lifetime future.
+            let static_self: &'static mut Self = unsafe {&mut *(self as *mut _)};
+
+            wasm_bindgen_futures::future_to_promise(async move {
+                #method(static_self, #from_js).await.map(#into_js)
+                    .map_err(wasm_bindgen::JsValue::from)
+            })
+        }
+    }
+}
+
+/// Turn the `new` method into the constructor of the JS class, which receives the raw `ObjectState`.
+fn expand_new(mut method: ImplItemMethod) -> syn::Result<TokenStream> {
+    let pound = syn::Token![#](method.span()).to_token_stream();
+    method.sig.ident = Ident::new("_new", method.sig.ident.span());
+
+    if method.sig.inputs.len() != 2 {
+        return Err(Error::new_spanned(
+            &method.sig.inputs,
+            "DurableObject `new` method must have 2 arguments: state and env",
+        ));
     }
+
+    // modify the `state` argument so it is type ObjectState
+    match &mut method.sig.inputs[0] {
+        FnArg::Typed(pat) => {
+            let path = syn::parse2::<TypePath>(quote! {worker_sys::durable_object::ObjectState})?;
+            *pat.ty = Type::Path(path);
+        }
+        arg => {
+            return Err(Error::new_spanned(
+                arg,
+                "DurableObject `new` method expects `state: State` as first argument.",
+            ))
+        }
+    }
+
+    // prepend the function block's statements to convert the ObjectState to State type
+    let mut prepended = vec![syn::parse_quote! {
+        let state = ::worker::durable::State::from(state);
+    }];
+    prepended.extend(method.block.stmts);
+    method.block.stmts = prepended;
+
+    Ok(quote! {
+        #pound[wasm_bindgen::prelude::wasm_bindgen(constructor)]
+        pub #method
+    })
 }
@@ -0,0 +1,290 @@
+use proc_macro2::{Ident, TokenStream};
+use quote::{format_ident, quote};
+use syn::{
+    Attribute, Error, FnArg, GenericArgument, ImplItem, ImplItemMethod, ItemImpl, Lit, Meta,
+    NestedMeta, Pat, PathArguments, ReturnType, Type, Visibility,
+};
+
+pub fn expand_macro(attr: TokenStream, tokens: TokenStream) -> syn::Result<TokenStream> {
+    if !attr.is_empty() {
+        return Err(Error::new_spanned(
+            attr,
+            "durable_object_rpc doesn't take any arguments",
+        ));
+    }
+
+    let mut imp = syn::parse2::<ItemImpl>(tokens)?;
+    if let Some((_, trai, _)) = &imp.trait_ {
+        return Err(Error::new_spanned(
+            trai,
+            "durable_object_rpc must be applied to an inherent impl of a Durable Object",
+        ));
+    }
+    if !imp.generics.params.is_empty() {
+        return Err(Error::new_spanned(
+            &imp.generics,
+            "durable_object_rpc doesn't support generic Durable Objects",
+        ));
+    }
+
+    let struct_name = match &*imp.self_ty {
+        Type::Path(path) if path.qself.is_none() => path.path.segments.last().map(|s| &s.ident),
+        _ => None,
+    }
+    .ok_or_else(|| Error::new_spanned(&imp.self_ty, "expected the name of a Durable Object"))?
+    .clone();
+    let stub_name = format_ident!("{}Stub", struct_name);
+
+    let mut methods = vec![];
+    for item in &mut imp.items {
+        if let ImplItem::Method(method) = item {
+            if let Some(method) = RpcMethod::parse(method)? {
+                methods.push(method);
+            }
+        }
+    }
+
+    let dispatch_arms = methods.iter().map(RpcMethod::dispatch_arm);
+    let client_methods = methods.iter().map(RpcMethod::client_method);
+    let stub_doc = format!(
+        "A stub calling the RPC methods of [`{}`] on a remote instance of the Durable Object.",
+        struct_name
+    );
+
+    Ok(quote! {
+        #imp
+
+        impl #struct_name {
+            /// Handles a call from the generated stub to one of the RPC methods. Requests for which
+            /// `worker::is_rpc_request` is false are answered with a 404.
+            pub async fn handle_rpc(
+                &mut self,
+                req: ::worker::Request,
+            ) -> ::worker::Result<::worker::Response> {
+                let mut call = match ::worker::durable::RpcCall::from_request(req) {
+                    Some(call) => call,
+                    None => return ::worker::Response::error("Not Found", 404),
+                };
+
+                let method = call.method().to_string();
+                match (method.as_str(), call.version()) {
+                    #(#dispatch_arms)*
+                    _ => call.not_found(),
+                }
+            }
+        }
+
+        #[doc = #stub_doc]
+        pub struct #stub_name {
+            stub: ::worker::durable::Stub,
+        }
+
+        impl ::std::convert::From<::worker::durable::Stub> for #stub_name {
+            fn from(stub: ::worker::durable::Stub) -> Self {
+                Self { stub }
+            }
+        }
+
+        impl #stub_name {
+            /// The underlying stub, for sending requests to the Durable Object's `fetch`.
+            pub fn stub(&self) -> &::worker::durable::Stub {
+                &self.stub
+            }
+
+            #(#client_methods)*
+        }
+    })
+}
+
+/// A `pub async fn` of the impl block, which is exposed over RPC.
+struct RpcMethod {
+    ident: Ident,
+    version: u32,
+    docs: Vec<Attribute>,
+    arg_names: Vec<Ident>,
+    arg_types: Vec<Type>,
+    ok: Type,
+    /// The error type sent over the wire, or `None` for `worker::Result` methods whose errors are
+    /// sent as strings.
+    err: Option<Type>,
+}
+
+impl RpcMethod {
+    fn parse(method: &mut ImplItemMethod) -> syn::Result<Option<Self>> {
+        let version = take_version(&mut method.attrs)?;
+        let exposed = matches!(method.vis, Visibility::Public(_))
+            && method.sig.asyncness.is_some()
+            && method.sig.receiver().is_some();
+        if !exposed {
+            return match version {
+                Some(_) => Err(Error::new_spanned(
+                    &method.sig,
+                    "only `pub async fn`s taking `self` are exposed over RPC",
+                )),
+                None => Ok(None),
+            };
+        }
+
+        let sig = &method.sig;
+        if !sig.generics.params.is_empty() {
+            return Err(Error::new_spanned(
+                &sig.generics,
+                "RPC methods can't be generic",
+            ));
+        }
+
+        let mut arg_names = vec![];
+        let mut arg_types = vec![];
+        for (i, arg) in sig.inputs.iter().enumerate() {
+            if let FnArg::Typed(arg) = arg {
+                let name = match &*arg.pat {
+                    Pat::Ident(pat) if pat.by_ref.is_none() && pat.subpat.is_none() => {
+                        pat.ident.clone()
+                    }
+                    _ => format_ident!("arg{}", i),
+                };
+                arg_names.push(name);
+                arg_types.push((*arg.ty).clone());
+            }
+        }
+
+        let (ok, err) = result_types(&sig.output)?;
+
+        Ok(Some(Self {
+            ident: sig.ident.clone(),
+            version: version.unwrap_or(1),
+            docs: method
+                .attrs
+                .iter()
+                .filter(|attr| attr.path.is_ident("doc"))
+                .cloned()
+                .collect(),
+            arg_names,
+            arg_types,
+            ok,
+            err,
+        }))
+    }
+
+    fn dispatch_arm(&self) -> TokenStream {
+        let Self {
+            ident,
+            version,
+            arg_names,
+            arg_types,
+            err,
+            ..
+        } = self;
+        let name = ident.to_string();
+        let result = match err {
+            Some(_) => quote! { result },
+            None => quote! { result.map_err(|e| e.to_string()) },
+        };
+
+        quote! {
+            (#name, #version) => {
+                let (#(#arg_names,)*): (#(#arg_types,)*) = match call.args().await {
+                    Ok(args) => args,
+                    Err(e) => return ::worker::durable::RpcCall::fail(e),
+                };
+                let result = self.#ident(#(#arg_names),*).await;
+                ::worker::durable::RpcCall::respond(#result)
+            }
+        }
+    }
+
+    fn client_method(&self) -> TokenStream {
+        let Self {
+            ident,
+            version,
+            docs,
+            arg_names,
+            arg_types,
+            ok,
+            err,
+        } = self;
+        let name = ident.to_string();
+        let err = match err {
+            Some(err) => quote! { #err },
+            None => quote! { ::std::string::String },
+        };
+
+        quote! {
+            #(#docs)*
+            pub async fn #ident(
+                &self,
+                #(#arg_names: #arg_types),*
+            ) -> ::std::result::Result<#ok, ::worker::durable::RpcError<#err>> {
+                ::worker::durable::RpcCall::send(
+                    &self.stub,
+                    #name,
+                    #version,
+                    &(#(#arg_names,)*),
+                )
+                .await
+            }
+        }
+    }
+}
+
+/// Removes the `#[rpc(version = N)]` attribute from a method, returning the version.
+fn take_version(attrs: &mut Vec<Attribute>) -> syn::Result<Option<u32>> {
+    let mut version = None;
+    let mut result = Ok(());
+
+    attrs.retain(|attr| {
+        if !attr.path.is_ident("rpc") {
+            return true;
+        }
+        if result.is_ok() {
+            result = parse_version(attr).map(|v| version = Some(v));
+        }
+        false
+    });
+
+    result.map(|_| version)
+}
+
+fn parse_version(attr: &Attribute) -> syn::Result<u32> {
+    let error = || Error::new_spanned(attr, "expected `#[rpc(version = N)]`");
+
+    let list = match attr.parse_meta()? {
+        Meta::List(list) if list.nested.len() == 1 => list,
+        _ => return Err(error()),
+    };
+    match &list.nested[0] {
+        NestedMeta::Meta(Meta::NameValue(nv)) if nv.path.is_ident("version") => match &nv.lit {
+            Lit::Int(int) => int.base10_parse(),
+            _ => Err(error()),
+        },
+        _ => Err(error()),
+    }
+}
+
+/// Splits a method's `Result<T, E>` or `worker::Result<T>` return type into `T` and `E`.
+fn result_types(output: &ReturnType) -> syn::Result<(Type, Option<Type>)> {
+    let error = || Error::new_spanned(output, "RPC methods must return a `Result`");
+
+    let ty = match output {
+        ReturnType::Type(_, ty) => ty,
+        ReturnType::Default => return Err(error()),
+    };
+    let segment = match &**ty {
+        Type::Path(path) => path.path.segments.last().ok_or_else(error)?,
+        _ => return Err(error()),
+    };
+    if segment.ident != "Result" {
+        return Err(error());
+    }
+    let args = match &segment.arguments {
+        PathArguments::AngleBracketed(args) => &args.args,
+        _ => return Err(error()),
+    };
+
+    let mut types = args.iter().filter_map(|arg| match arg {
+        GenericArgument::Type(ty) => Some(ty.clone()),
+        _ => None,
+    });
+    let ok = types.next().ok_or_else(error)?;
+    Ok((ok, types.next()))
+}
@@ -1,9 +1,34 @@
 use proc_macro::TokenStream;
 use quote::quote;
-use syn::{parse_macro_input, punctuated::Punctuated, token::Comma, Ident, ItemFn};
+use syn::{
+    parse::{Parse, ParseStream},
+    parse_macro_input,
+    punctuated::Punctuated,
+    token::Comma,
+    Ident, ItemFn, Path, Token,
+};
+
+/// An argument to the `#[event]` attribute, either a flag such as `fetch` or a named value such as
+/// `error_handler = handle_error`.
+enum EventArg {
+    Flag(Ident),
+    Value(Ident, Path),
+}
+
+impl Parse for EventArg {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        let name = input.parse()?;
+        if input.peek(Token![=]) {
+            input.parse::<Token![=]>()?;
+            Ok(EventArg::Value(name, input.parse()?))
+        } else {
+            Ok(EventArg::Flag(name))
+        }
+    }
+}
 
 pub fn expand_macro(attr: TokenStream, item: TokenStream) -> TokenStream {
-    let attrs: Punctuated<Ident, Comma> =
+    let attrs: Punctuated<EventArg, Comma> =
         parse_macro_input!(attr with Punctuated::parse_terminated);
 
     enum HandlerType {
@@ -18,8 +43,18 @@ pub fn expand_macro(attr: TokenStream, item: TokenStream) -> TokenStream {
     let mut handler_type = None;
     let mut respond_with_errors = false;
     let mut http_support = false;
+    let mut error_handler = None;
 
     for attr in attrs {
+        let attr = match attr {
+            EventArg::Flag(attr) => attr,
+            EventArg::Value(name, path) if name == "error_handler" => {
+                error_handler = Some(path);
+                continue;
+            }
+            EventArg::Value(name, _) => panic!("Invalid attribute: {}", name),
+        };
+
         match attr.to_string().as_str() {
             "fetch" => handler_type = Some(Fetch),
             "scheduled" => handler_type = Some(Scheduled),
@@ -38,6 +73,9 @@ pub fn expand_macro(attr: TokenStream, item: TokenStream) -> TokenStream {
     let handler_type = handler_type.expect(
         "must have either 'fetch', 'scheduled', 'queue' or 'start' attribute, e.g. #[event(fetch)]",
     );
+    if error_handler.is_some() && !matches!(handler_type, Fetch) {
+        panic!("the 'error_handler' attribute is only supported with 'fetch'");
+    }
 
     // create new var using syn item of the attributed fn
     let mut input_fn = parse_macro_input!(item as ItemFn);
@@ -57,7 +95,7 @@ pub fn expand_macro(attr: TokenStream, item: TokenStream) -> TokenStream {
             let error_handling = match respond_with_errors {
                 true => {
                     quote! {
-                        ::worker::Response::error(e.to_string(), 500).unwrap().into()
+                        ::worker::Response::error(e.to_string(), e.status_code()).unwrap().into()
                     }
                 }
                 false => {
@@ -65,13 +103,41 @@ pub fn expand_macro(attr: TokenStream, item: TokenStream) -> TokenStream {
                 }
             };
 
+            // the error handler is wrapped in a fn next to the original one, so that its path
+            // resolves the same way it would for the user
+            let error_glue_ident = Ident::new(
+                &(input_fn.sig.ident.to_string() + "_error_glue"),
+                input_fn.sig.ident.span(),
+            );
+            let (error_glue, error_handling) = match error_handler {
+                Some(error_handler) => (
+                    quote! {
+                        fn #error_glue_ident(e: ::worker::Error) -> ::worker::Result<::worker::Response> {
+                            ::worker::IntoResponse::into_response(#error_handler(e))
+                        }
+                    },
+                    quote! {
+                        match super::#error_glue_ident(e) {
+                            Ok(resp) => resp.into(),
+                            Err(e) => {
+                                ::worker::console_log!("{}", &e);
+                                #error_handling
+                            }
+                        }
+                    },
+                ),
+                None => (quote! {}, error_handling),
+            };
+
             let request_transform = if http_support {
                 quote! {
-                    let request: ::worker::http::Request<::worker::ByteStream> = ::worker::http_types::EdgeRequest(req).try_into().unwrap();
+                    let request = <::worker::HttpRequest as ::std::convert::TryFrom<_>>::try_from(
+                        ::worker::http_types::EdgeRequest(req),
+                    );
                 }
             } else {
                 quote! {
-                    let request = ::worker::Request::from(req);
+                    let request = Ok(::worker::Request::from(req));
                 }
             };
 
@@ -83,18 +149,20 @@ pub fn expand_macro(attr: TokenStream, item: TokenStream) -> TokenStream {
                     env: ::worker::Env,
                     ctx: ::worker::worker_sys::Context
                 ) -> ::worker::worker_sys::Response {
-                    use std::convert::{TryFrom, TryInto};
-
                     let ctx = worker::Context::new(ctx);
 
                     #request_transform
 
-                    // get the worker::Result<worker::Response> by calling the original fn
-                    match #input_fn_ident(request, env, ctx).await {
-                        Ok(resp) => {
-                            let resp: ::worker::Response = resp.try_into().unwrap();
-                            resp.try_into().unwrap()
-                        },
+                    // call the original fn and convert whatever it returned into a worker::Response,
+                    // unless the request couldn't be converted for it
+                    let result = match request {
+                        Ok(request) => ::worker::IntoResponse::into_response(
+                            #input_fn_ident(request, env, ctx).await
+                        ),
+                        Err(e) => Err(e),
+                    };
+                    match result {
+                        Ok(resp) => resp.into(),
                         Err(e) => {
                             ::worker::console_log!("{}", &e);
                             #error_handling
@@ -109,6 +177,8 @@ pub fn expand_macro(attr: TokenStream, item: TokenStream) -> TokenStream {
             let output = quote! {
                 #input_fn
 
+                #error_glue
+
                 mod _worker_fetch {
                     use ::worker::{wasm_bindgen, wasm_bindgen_futures};
                     use super::#input_fn_ident;
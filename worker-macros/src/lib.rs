@@ -1,4 +1,5 @@
 mod durable_object;
+mod durable_object_rpc;
 mod event;
 
 use proc_macro::TokenStream;
@@ -10,6 +11,13 @@ pub fn durable_object(_attr: TokenStream, item: TokenStream) -> TokenStream {
         .into()
 }
 
+#[proc_macro_attribute]
+pub fn durable_object_rpc(attr: TokenStream, item: TokenStream) -> TokenStream {
+    durable_object_rpc::expand_macro(attr.into(), item.into())
+        .unwrap_or_else(syn::Error::into_compile_error)
+        .into()
+}
+
 #[proc_macro_attribute]
 pub fn event(attr: TokenStream, item: TokenStream) -> TokenStream {
     event::expand_macro(attr, item)
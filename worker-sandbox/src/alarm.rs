@@ -14,9 +14,9 @@ impl DurableObject for AlarmObject {
     }
 
     async fn fetch(&mut self, _: Request) -> Result<Response> {
-        let alarmed: bool = match self.state.storage().get("alarmed").await {
-            Ok(alarmed) => alarmed,
-            Err(e) if e.to_string() == "No such value in storage." => {
+        let alarmed: bool = match self.state.storage().get("alarmed").await? {
+            Some(alarmed) => alarmed,
+            None => {
                 // Trigger our alarm method in 100ms.
                 self.state
                     .storage()
@@ -25,16 +25,15 @@ impl DurableObject for AlarmObject {
 
                 false
             }
-            Err(e) => return Err(e),
         };
 
         Response::ok(alarmed.to_string())
     }
 
-    async fn alarm(&mut self) -> Result<Response> {
+    async fn alarm(&mut self, info: AlarmInvocationInfo) -> Result<Response> {
         self.state.storage().put("alarmed", true).await?;
 
-        console_log!("Alarm has been triggered!");
+        console_log!("Alarm has been triggered! (retry {})", info.retry_count);
 
         Response::ok("ALARMED")
     }
@@ -1,3 +1,4 @@
+use serde::{Deserialize, Serialize};
 use worker::*;
 
 #[durable_object]
@@ -19,14 +20,12 @@ impl DurableObject for Counter {
         }
     }
 
-    async fn fetch(&mut self, _req: Request) -> Result<Response> {
-        if !self.initialized {
-            self.initialized = true;
-            self.count = self.state.storage().get("count").await.unwrap_or(0);
+    async fn fetch(&mut self, req: Request) -> Result<Response> {
+        if is_rpc_request(&req) {
+            return self.handle_rpc(req).await;
         }
 
-        self.count += 10;
-        self.state.storage().put("count", self.count).await?;
+        self.add(10).await?;
 
         Response::ok(format!(
             "[durable_object]: self.count: {}, secret value: {}",
@@ -35,3 +34,69 @@ impl DurableObject for Counter {
         ))
     }
 }
+
+#[derive(Debug, Serialize, Deserialize)]
+pub enum CountError {
+    Underflow { count: usize },
+    Storage(String),
+}
+
+impl From<Error> for CountError {
+    fn from(e: Error) -> Self {
+        CountError::Storage(e.to_string())
+    }
+}
+
+impl std::fmt::Display for CountError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            CountError::Underflow { count } => write!(f, "count is only {}", count),
+            CountError::Storage(e) => write!(f, "storage error: {}", e),
+        }
+    }
+}
+
+#[durable_object_rpc]
+impl Counter {
+    /// Adds to the count, returning the new count.
+    pub async fn add(&mut self, amount: usize) -> Result<usize> {
+        self.load().await?;
+        self.count += amount;
+        // a lost increment is acceptable, so don't hold back responses until it's durable
+        let options = PutOptions {
+            allow_unconfirmed: Some(true),
+            ..PutOptions::default()
+        };
+        self.state
+            .storage()
+            .put_with_options("count", self.count, options)
+            .await?;
+        Ok(self.count)
+    }
+
+    /// Subtracts from the count, failing if it would drop below zero.
+    pub async fn subtract(&mut self, amount: usize) -> std::result::Result<usize, CountError> {
+        self.load().await?;
+        self.count = self
+            .count
+            .checked_sub(amount)
+            .ok_or(CountError::Underflow { count: self.count })?;
+        self.state.storage().put("count", self.count).await?;
+        Ok(self.count)
+    }
+
+    async fn load(&mut self) -> Result<()> {
+        if !self.initialized {
+            // hold other requests back until the count is loaded, so they don't see it as 0
+            let storage = self.state.storage();
+            self.count = self
+                .state
+                .block_concurrency_while(
+                    move || async move { storage.get_or_default("count").await },
+                )
+                .await?;
+            self.initialized = true;
+        }
+        Ok(())
+    }
+}
@@ -11,6 +11,7 @@ use futures_util::{future::Either, StreamExt, TryStreamExt};
 use rand::Rng;
 use serde::{Deserialize, Serialize};
 use uuid::Uuid;
+use worker::extract::{Json, Path, Query};
 use worker::*;
 
 mod alarm;
@@ -95,8 +96,44 @@ pub async fn main(req: worker::Request, env: Env, _ctx: worker::Context) -> Resu
 
     let router = Router::with_data(data); // if no data is needed, pass `()` or any other valid data
 
+    // Handlers can also be closures which capture state from when the router was built.
+    let greeting = format!("Hello from {}!", req.path());
+
     router
+        .middleware_for("/middleware/*route", |req, ctx, next| async move {
+            // Reject requests which don't carry the expected header before reaching the handler.
+            if req.headers().get("x-middleware-key")?.as_deref() != Some("open sesame") {
+                return Response::error("Unauthorized", 401);
+            }
+
+            let mut res = next.run(req, ctx).await?;
+            res.headers_mut().set("x-middleware", "ran")?;
+            Ok(res)
+        })
+        .get("/middleware/hello", |_, _| Response::ok("hello from behind middleware"))
+        .mount("/mounted/:org", mounted_router()) // routers can be nested under a prefix
+        .mount("/problem", problem_router())
+        .mount("/cors-policy", cors_router())
         .get("/request", handle_a_request) // can pass a fn pointer to keep routes tidy
+        .get("/closure", {
+            let greeting = greeting.clone();
+            move |_, _| Response::ok(&greeting)
+        })
+        .get_async("/closure-async", move |_, _| {
+            let greeting = greeting.clone();
+            async move { Response::ok(greeting) }
+        })
+        // Handlers can declare typed arguments, which are extracted from the request for them.
+        .post_async("/extract/:org/users/:id", extract::handler(extracted_user))
+        // Handlers can return anything implementing `IntoResponse`, not just a `Response`.
+        .get("/into-response/str", |_, _| "hello, world")
+        .get("/into-response/json", |_, _| {
+            (201, Json(serde_json::json!({ "created": true })))
+        })
+        .get("/into-response/headers", |_, _| {
+            let headers: Headers = [("x-custom", "value")].iter().collect();
+            (headers, String::from("with headers"))
+        })
         .get_async("/async-request", handle_async_request)
         .get("/websocket", |_, ctx| {
             // Accept / handle a websocket connection
@@ -363,9 +400,28 @@ pub async fn main(req: worker::Request, env: Env, _ctx: worker::Context) -> Resu
             // https://developers.cloudflare.com/workers/platform/compatibility-dates#durable-object-stubfetch-requires-a-full-url
             stub.fetch_with_str("https://fake-host/alarm").await
         })
+        .get_async("/durable/websocket/:name", |req, ctx| async move {
+            let name = ctx.param("name").ok_or("missing name")?;
+            let stub = ctx.durable_object("ECHO")?.get_by_name(name)?;
+            // the Echo object accepts the WebSocket with the hibernation API, so its handlers are
+            // called by the runtime rather than by event listeners
+            stub.fetch_with_request(req).await
+        })
+        .get_async("/durable/rpc", |_req, ctx| async move {
+            let namespace = ctx.durable_object("COUNTER")?;
+            let counter = counter::CounterStub::from(namespace.unique_id()?.get_stub()?);
+
+            let count = counter.add(5).await?;
+            match counter.subtract(count + 1).await {
+                Err(RpcError::Method(counter::CountError::Underflow { count })) => {
+                    Response::ok(format!("underflow at {}", count))
+                }
+                other => Response::error(format!("expected an underflow, got {:?}", other), 500),
+            }
+        })
         .get_async("/durable/:id", |_req, ctx| async move {
             let namespace = ctx.durable_object("COUNTER")?;
-            let stub = namespace.id_from_name("A")?.get_stub()?;
+            let stub = namespace.get_by_name("A")?;
             // when calling fetch to a Durable Object, a full URL must be used. Alternatively, a
             // compatibility flag can be provided in wrangler.toml to opt-in to older behavior:
             // https://developers.cloudflare.com/workers/platform/compatibility-dates#durable-object-stubfetch-requires-a-full-url
@@ -467,7 +523,7 @@ pub async fn main(req: worker::Request, env: Env, _ctx: worker::Context) -> Resu
                 Response::ok(text).unwrap()
             });
 
-            Ok(res)
+            Ok::<Response, worker::Error>(res)
         })
         .get_async("/fetch-timeout", |_, _| async move {
             let controller = AbortController::default();
@@ -578,7 +634,7 @@ pub async fn main(req: worker::Request, env: Env, _ctx: worker::Context) -> Resu
             let key = req.url()?.to_string();
             if let Some(resp) = cache.get(&key, true).await? {
                 console_log!("Cache HIT!");
-                Ok(resp)
+                Ok::<Response, worker::Error>(resp)
             } else {
 
                 console_log!("Cache MISS!");
@@ -631,7 +687,7 @@ pub async fn main(req: worker::Request, env: Env, _ctx: worker::Context) -> Resu
             let key = req.url()?.to_string();
             if let Some(resp) = cache.get(&key, true).await? {
                 console_log!("Cache HIT!");
-                Ok(resp)
+                Ok::<Response, worker::Error>(resp)
             } else {
                 console_log!("Cache MISS!");
                 let mut rng = rand::thread_rng();
@@ -688,7 +744,33 @@ pub async fn main(req: worker::Request, env: Env, _ctx: worker::Context) -> Resu
                     Response::error(format!("Failed to send message to queue: {err:?}"), 500)
                 }
             }
-        }).get_async("/queue", |_req, _ctx| async move {
+        })
+        .post_async("/queue/send_batch/:id", |_req, ctx| async move {
+            let id = match ctx.param("id").and_then(|id| Uuid::try_parse(id).ok()) {
+                Some(id) => id,
+                None => return Response::error("Failed to parse id, expected a UUID", 400),
+            };
+            let my_queue = ctx.env.queue("my_queue")?;
+            let message = SendMessage::new(
+                QueueBody {
+                    id,
+                    id_string: id.to_string(),
+                },
+                QueueSendOptions {
+                    content_type: Some(QueueContentType::Json),
+                    ..QueueSendOptions::default()
+                },
+            );
+
+            match my_queue.send_batch::<QueueBody, _>(vec![message]).await?.pop() {
+                None => Response::ok("Batch sent"),
+                Some(failed) => Response::error(
+                    format!("Failed to send batch to queue: {:?}", failed.error),
+                    500,
+                ),
+            }
+        })
+        .get_async("/queue", |_req, _ctx| async move {
             let guard = GLOBAL_QUEUE_STATE.lock().unwrap();
             let messages: Vec<QueueBody> = guard.clone();
             Response::from_json(&messages)
@@ -733,6 +815,97 @@ mod fetch_http {
     }
 }
 
+fn mounted_router<'a>() -> Router<'a, &'static str> {
+    Router::with_data("mounted")
+        .get("/", |req, ctx| {
+            Response::ok(format!("{} root at {}", ctx.data, req.path()))
+        })
+        .get("/users/:id", |req, ctx| {
+            Response::ok(format!(
+                "{}: user {} of {} at {}",
+                ctx.data,
+                ctx.param("id").unwrap(),
+                ctx.param("org").unwrap(),
+                req.path()
+            ))
+        })
+        .get_async("/teams/:team/:member", extract::handler(mounted_member))
+}
+
+async fn mounted_member(Path((team, member)): Path<(String, String)>) -> String {
+    format!("{} of team {}", member, team)
+}
+
+fn cors_router<'a>() -> Router<'a, ()> {
+    Router::new()
+        .get("/hello", |_, _| "hello from a cors policy")
+        .get("/error", |_, _| -> Result<Response> {
+            Err(Error::RustError("error from a cors policy".into()))
+        })
+        .on_error(|e| Response::error(format!("handled {e}"), 500))
+        .cors(
+            Cors::new()
+                .with_origins(["https://example.com", "https://*.example.org"])
+                .with_methods([Method::Put])
+                .with_exposed_headers(["x-custom"]),
+        )
+}
+
+#[derive(Debug)]
+struct TeapotError;
+
+impl std::fmt::Display for TeapotError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "this server is a teapot")
+    }
+}
+
+impl std::error::Error for TeapotError {}
+
+fn problem_router<'a>() -> Router<'a, ()> {
+    Router::new()
+        .get("/bad-encoding", |_, _| -> Result<Response> {
+            Err(Error::BadEncoding)
+        })
+        .get("/teapot", |_, _| -> Result<Response> {
+            Err(Error::custom(TeapotError))
+        })
+        // Handlers can also return problem details as their error type.
+        .get("/gone", gone)
+        // Errors returned by the handlers above are converted into JSON problem details.
+        .on_error(|e| match e.downcast_ref::<TeapotError>() {
+            Some(teapot) => ProblemDetails::new(418).with_detail(teapot.to_string()),
+            None => ProblemDetails::from(e),
+        })
+}
+
+#[allow(clippy::result_large_err)]
+fn gone(_: Request, _: RouteContext<()>) -> std::result::Result<Response, ProblemDetails> {
+    Err(ProblemDetails::new(410).with_detail("this route has been removed"))
+}
+
+#[derive(Deserialize)]
+struct ExtractedQuery {
+    verbose: Option<bool>,
+}
+
+#[derive(Deserialize)]
+struct ExtractedUser {
+    name: String,
+}
+
+async fn extracted_user(
+    Path((org, id)): Path<(String, u64)>,
+    Query(query): Query<ExtractedQuery>,
+    Json(user): Json<ExtractedUser>,
+) -> Result<Response> {
+    let mut body = format!("user {} of {} renamed to {}", id, org, user.name);
+    if query.verbose.unwrap_or_default() {
+        body.push_str(" (verbose)");
+    }
+    Response::ok(body)
+}
+
 fn respond<D>(req: Request, _ctx: RouteContext<D>) -> Result<Response> {
     Response::ok(format!("Ok: {}", String::from(req.method()))).map(|resp| {
         let mut headers = Headers::new();
@@ -760,10 +933,11 @@ pub async fn queue(message_batch: MessageBatch<QueueBody>, _env: Env, _ctx: Cont
     let mut guard = GLOBAL_QUEUE_STATE.lock().unwrap();
     for message in message_batch.messages()? {
         console_log!(
-            "Received queue message {:?}, with id {} and timestamp: {}",
+            "Received queue message {:?}, with id {}, timestamp: {} and attempts: {}",
             message.body,
             message.id,
-            message.timestamp.to_string()
+            message.timestamp.to_string(),
+            message.attempts
         );
         guard.push(message.body);
     }
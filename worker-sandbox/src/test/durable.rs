@@ -47,5 +47,17 @@ pub async fn basic_test(env: &Env) -> Result<()> {
         "Durable object responded wrong to 'storage'"
     );
 
+    let res = stub.fetch_with_str("typed").await?.text().await?;
+    ensure!(
+        res == "ok",
+        "Durable object responded wrong to 'typed': ".to_string() + &res
+    );
+
+    let res = stub.fetch_with_str("persisted").await?.text().await?;
+    ensure!(
+        res == "ok",
+        "Durable object responded wrong to 'persisted': ".to_string() + &res
+    );
+
     Ok(())
 }
@@ -1,3 +1,4 @@
+use futures_util::TryStreamExt;
 use serde::Serialize;
 use std::collections::HashMap;
 
@@ -46,26 +47,29 @@ impl DurableObject for MyClass {
                         format!("Didn't list all of the keys: {keys:?}")
                     );
                     let vals = storage
-                        .get_multiple(keys)
+                        .get_multiple::<Option<i32>>(vec!["anything", "missing"])
                         .await
                         .map_err(|e| e.to_string() + " -- get_multiple")?;
                     ensure!(
-                        serde_wasm_bindgen::from_value::<Option<i32>>(
-                            vals.get(&"anything".into())
-                        )? == Some(45),
+                        vals.len() == 1 && vals.get("anything") == Some(&Some(45)),
                         "Didn't get the right Option<i32> using get_multiple"
                     );
                     ensure!(
-                        serde_wasm_bindgen::from_value::<[(String, i32); 2]>(
-                            vals.get(&"array".into())
-                        )? == [("one".to_string(), 1), ("two".to_string(), 2)],
-                        "Didn't get the right array using get_multiple"
+                        storage.get::<[(String, i32); 2]>("array").await?
+                            == Some([("one".to_string(), 1), ("two".to_string(), 2)]),
+                        "Didn't get the right array using get"
                     );
                     ensure!(
-                        serde_wasm_bindgen::from_value::<HashMap<String, i32>>(
-                            vals.get(&"map".into())
-                        )? == map,
-                        "Didn't get the right HashMap<String, i32> using get_multiple"
+                        storage.get::<HashMap<String, i32>>("map").await? == Some(map),
+                        "Didn't get the right HashMap<String, i32> using get"
+                    );
+                    ensure!(
+                        storage.get::<i32>("missing").await?.is_none()
+                            && matches!(
+                                storage.get_required::<i32>("missing").await,
+                                Err(Error::KeyNotFound(_))
+                            ),
+                        "Didn't get nothing for a missing key"
                     );
 
                     #[derive(Serialize)]
@@ -81,17 +85,17 @@ impl DurableObject for MyClass {
                         .await?;
 
                     ensure!(
-                        storage.get::<String>("thing").await? == "Hello there",
+                        storage.get_required::<String>("thing").await? == "Hello there",
                         "Didn't put the right thing with put_multiple"
                     );
                     ensure!(
-                        storage.get::<i32>("other").await? == 56,
+                        storage.get_required::<i32>("other").await? == 56,
                         "Didn't put the right thing with put_multiple"
                     );
 
                     storage.delete_multiple(vec!["thing", "other"]).await?;
 
-                    self.number = storage.get("count").await.unwrap_or(0) + 1;
+                    self.number = storage.get_or_default::<usize>("count").await? + 1;
 
                     storage.delete_all().await?;
 
@@ -99,7 +103,126 @@ impl DurableObject for MyClass {
                     Response::ok(self.number.to_string())
                 }
                 "/transaction" => {
-                    Response::error("transactional storage API is still unstable", 501)
+                    let mut storage = self.state.storage();
+
+                    let rolled_back = storage
+                        .transaction(|mut txn| async move {
+                            txn.put("count", 0).await?;
+                            Err::<(), _>("rolled back".into())
+                        })
+                        .await;
+                    ensure!(
+                        rolled_back.is_err() && storage.get::<usize>("count").await? != Some(0),
+                        "Didn't roll back the transaction"
+                    );
+
+                    self.number = storage
+                        .transaction(|mut txn| async move {
+                            let count = txn.get_or_default::<usize>("count").await? + 1;
+                            txn.put("count", count).await?;
+                            Ok(count)
+                        })
+                        .await?;
+                    Response::ok(self.number.to_string())
+                }
+                "/typed" => {
+                    let mut ledger = self.state.storage().typed::<(String, u32), i64>("ledger");
+                    ledger.put(&("alice".into(), 7), &-20).await?;
+                    ledger.put(&("alice".into(), 12), &45).await?;
+                    ledger.put(&("bob".into(), 9), &3).await?;
+
+                    ensure!(
+                        ledger.get(&("alice".into(), 12)).await? == Some(45),
+                        "Didn't get the right value from typed storage"
+                    );
+                    ensure!(
+                        ledger.list_prefix(&("alice",)).await?
+                            == vec![(("alice".into(), 7), -20), (("alice".into(), 12), 45)],
+                        "Didn't list the right prefix from typed storage"
+                    );
+
+                    let entries = ledger
+                        .stream_range(("alice".into(), 10).., 1)?
+                        .try_collect::<Vec<_>>()
+                        .await?;
+                    ensure!(
+                        entries == vec![(("alice".into(), 12), 45), (("bob".into(), 9), 3)],
+                        "Didn't stream the right range from typed storage"
+                    );
+
+                    ensure!(
+                        ledger.delete(&("bob".into(), 9)).await?
+                            && ledger.list_range(..).await?.len() == 2,
+                        "Didn't delete from typed storage"
+                    );
+                    Response::ok("ok")
+                }
+                "/persisted" => {
+                    let mut visits = self.state.persisted::<u32>("visits");
+                    *visits.get_mut().await? += 1;
+                    let expected = *visits.get().await?;
+                    visits.flush().await?;
+
+                    // a value twice the chunk size takes a few chunks
+                    let mut chunked = self
+                        .state
+                        .persisted::<Vec<String>>("chunked")
+                        .with_chunk_size(16);
+                    chunked.set(vec!["0123456789".into(); 3]);
+                    ensure!(chunked.is_dirty(), "Persisted value wasn't dirty after set");
+                    chunked.flush().await?;
+
+                    let mut visits = self.state.persisted::<u32>("visits");
+                    let mut chunked = self
+                        .state
+                        .persisted::<Vec<String>>("chunked")
+                        .with_chunk_size(16);
+                    ensure!(
+                        *visits.get().await? == expected,
+                        "Didn't load the persisted value"
+                    );
+                    ensure!(
+                        chunked.get().await?.len() == 3 && !chunked.is_dirty(),
+                        "Didn't load the chunked persisted value"
+                    );
+
+                    // a smaller value set without loading the stored one replaces all of its chunks
+                    let mut chunked = self
+                        .state
+                        .persisted::<Vec<String>>("chunked")
+                        .with_chunk_size(16);
+                    chunked.set(vec!["0".into()]);
+                    chunked.flush().await?;
+                    ensure!(
+                        self.state
+                            .storage()
+                            .get::<String>("chunked:1")
+                            .await?
+                            .is_none(),
+                        "Left stale chunks of the persisted value behind"
+                    );
+
+                    // a flush after a guard is dropped isn't overwritten by its background write
+                    let mut visits = self.state.persisted::<u32>("visits");
+                    *visits.modify().await? = 100;
+                    visits.set(200);
+                    visits.flush().await?;
+                    chunked.modify().await?.push("0123456789".into());
+                    chunked.set(vec!["0".into()]);
+                    chunked.flush().await?;
+
+                    let storage = self.state.storage();
+                    ensure!(
+                        storage.get::<u32>("visits").await? == Some(200),
+                        "A background write overwrote a later flush"
+                    );
+                    ensure!(
+                        storage.get::<usize>("chunked").await? == Some(1)
+                            && storage.get::<String>("chunked:0").await?.as_deref()
+                                == Some(r#"["0"]"#),
+                        "A background write overwrote a later flush of chunks"
+                    );
+                    Response::ok("ok")
                 }
                 _ => Response::error("Not Found", 404),
             }
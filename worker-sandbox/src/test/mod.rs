@@ -1,5 +1,6 @@
 pub mod durable;
 pub mod export_durable_object;
+pub mod websocket_durable_object;
 
 #[macro_export]
 macro_rules! ensure {
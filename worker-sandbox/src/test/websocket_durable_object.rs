@@ -0,0 +1,76 @@
+use serde::{Deserialize, Serialize};
+
+use worker::*;
+
+#[derive(Serialize, Deserialize)]
+struct Connection {
+    name: String,
+}
+
+#[durable_object]
+pub struct Echo {
+    state: State,
+}
+
+#[durable_object]
+impl DurableObject for Echo {
+    const AUTO_RESPONSE: (&'static str, &'static str) = ("ping", "pong");
+
+    fn new(state: State, _env: Env) -> Self {
+        Self { state }
+    }
+
+    async fn fetch(&mut self, req: Request) -> Result<Response> {
+        let name = req.path().trim_start_matches('/').to_string();
+        let pair = WebSocketPair::new()?;
+
+        self.state.accept_web_socket(&pair.server, &[&name])?;
+        pair.server.serialize_attachment(&Connection { name })?;
+        let (request, response) = Self::AUTO_RESPONSE;
+        self.state
+            .set_web_socket_auto_response(Some(&WebSocketRequestResponsePair::new(
+                request, response,
+            )?))?;
+
+        Response::from_websocket(pair.client)
+    }
+
+    async fn websocket_message(
+        &mut self,
+        ws: WebSocket,
+        message: WebSocketIncomingMessage,
+    ) -> Result<()> {
+        for peer in self.peers(&ws)? {
+            match &message {
+                WebSocketIncomingMessage::String(text) => peer.send_with_str(text)?,
+                WebSocketIncomingMessage::Binary(bytes) => peer.send_with_bytes(bytes)?,
+            }
+        }
+        Ok(())
+    }
+
+    async fn websocket_close(
+        &mut self,
+        ws: WebSocket,
+        code: u16,
+        reason: String,
+        _was_clean: bool,
+    ) -> Result<()> {
+        // codes which only report a connection's state, such as 1005 (no status) or 1006 (closed
+        // abnormally), can't be sent in a close frame
+        let code = match code {
+            1004 | 1005 | 1006 | 1015 => 1000,
+            1000..=4999 => code,
+            _ => 1000,
+        };
+        ws.close(Some(code), Some(reason))
+    }
+
+    /// The sockets connected under the same name as `ws`, including itself.
+    fn peers(&self, ws: &WebSocket) -> Result<Vec<WebSocket>> {
+        let connection = ws
+            .deserialize_attachment::<Connection>()?
+            .ok_or("WebSocket has no attachment")?;
+        self.state.get_web_sockets(Some(&connection.name))
+    }
+}
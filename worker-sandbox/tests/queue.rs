@@ -46,3 +46,29 @@ fn receive_message_from_queue() {
     assert_eq!(message.id, id);
     assert_eq!(message.id_string, id.to_string());
 }
+
+#[test]
+fn receive_batch_from_queue() {
+    // Arrange
+    expect_wrangler();
+    let id = Uuid::new_v4();
+
+    let send_batch_response = post(&format!("queue/send_batch/{id}"), |r| r);
+    assert!(send_batch_response.status().is_success());
+
+    // Act
+    let message = retry::retry(Fixed::from_millis(500).take(5), || {
+        let messages: Vec<QueueBody> = util::get("queue", |r| r)
+            .json()
+            .expect("Failed to get Json");
+
+        match messages.iter().find(|m| m.id == id) {
+            Some(m) => Ok(m.clone()),
+            None => Err("Failed to find expected message"),
+        }
+    })
+    .unwrap();
+
+    // Assert
+    assert_eq!(message.id_string, id.to_string());
+}
@@ -26,6 +26,204 @@ fn async_request() {
     let _ = get("async-request", |r| r);
 }
 
+#[test]
+fn closure() {
+    let body = get("closure", |r| r).text().unwrap();
+    assert_eq!(body, "Hello from /closure!");
+
+    let body = get("closure-async", |r| r).text().unwrap();
+    assert_eq!(body, "Hello from /closure-async!");
+}
+
+#[test]
+fn mounted_router() {
+    let body = get("mounted/acme", |r| r).text().unwrap();
+    assert_eq!(body, "mounted root at /");
+
+    let body = get("mounted/acme/users/7", |r| r).text().unwrap();
+    assert_eq!(body, "mounted: user 7 of acme at /users/7");
+
+    // the `:org` param of the mount prefix doesn't shift the route's own positional params
+    let body = get("mounted/acme/teams/rust/ferris", |r| r).text().unwrap();
+    assert_eq!(body, "ferris of team rust");
+}
+
+#[test]
+fn extractors() {
+    let body = post("extract/acme/users/7?verbose=true", |r| {
+        r.json(&serde_json::json!({ "name": "Ferris" }))
+    })
+    .text()
+    .unwrap();
+    assert_eq!(body, "user 7 of acme renamed to Ferris (verbose)");
+
+    let status = |path: &str, content_type: &str, body: &'static str| {
+        Client::new()
+            .post(format!("http://127.0.0.1:8787/{path}"))
+            .header("content-type", content_type)
+            .body(body)
+            .send()
+            .unwrap()
+            .status()
+    };
+    let json = "application/json";
+    let user = r#"{ "name": "Ferris" }"#;
+
+    assert_eq!(
+        status("extract/acme/users/seven", json, user),
+        StatusCode::BAD_REQUEST
+    );
+    assert_eq!(
+        status("extract/acme/users/7?verbose=maybe", json, user),
+        StatusCode::BAD_REQUEST
+    );
+    assert_eq!(
+        status("extract/acme/users/7", "text/plain", user),
+        StatusCode::UNSUPPORTED_MEDIA_TYPE
+    );
+    assert_eq!(
+        status("extract/acme/users/7", json, "{ \"name\""),
+        StatusCode::BAD_REQUEST
+    );
+    assert_eq!(
+        status("extract/acme/users/7", json, r#"{ "name": 7 }"#),
+        StatusCode::UNPROCESSABLE_ENTITY
+    );
+}
+
+#[test]
+fn into_response() {
+    let body = get("into-response/str", |r| r).text().unwrap();
+    assert_eq!(body, "hello, world");
+
+    let response = get("into-response/json", |r| r);
+    assert_eq!(response.status(), StatusCode::CREATED);
+    let body: serde_json::Value = response.json().unwrap();
+    assert_eq!(body, serde_json::json!({ "created": true }));
+
+    let response = get("into-response/headers", |r| r);
+    assert_eq!(
+        response
+            .headers()
+            .get("x-custom")
+            .map(|v| v.to_str().unwrap()),
+        Some("value")
+    );
+    assert_eq!(response.text().unwrap(), "with headers");
+}
+
+#[test]
+fn problem_details() {
+    expect_wrangler();
+
+    let response = reqwest::blocking::get("http://127.0.0.1:8787/problem/bad-encoding").unwrap();
+    assert_eq!(response.status(), StatusCode::UNSUPPORTED_MEDIA_TYPE);
+    assert_eq!(
+        response
+            .headers()
+            .get("content-type")
+            .map(|v| v.to_str().unwrap()),
+        Some("application/problem+json")
+    );
+    let body: serde_json::Value = response.json().unwrap();
+    assert_eq!(
+        body,
+        serde_json::json!({
+            "title": "Unsupported Media Type",
+            "status": 415,
+            "detail": "content-type mismatch",
+        })
+    );
+
+    let response = reqwest::blocking::get("http://127.0.0.1:8787/problem/teapot").unwrap();
+    assert_eq!(response.status().as_u16(), 418);
+    let body: serde_json::Value = response.json().unwrap();
+    assert_eq!(body["detail"], "this server is a teapot");
+
+    let response = reqwest::blocking::get("http://127.0.0.1:8787/problem/gone").unwrap();
+    assert_eq!(response.status(), StatusCode::GONE);
+    let body: serde_json::Value = response.json().unwrap();
+    assert_eq!(body["detail"], "this route has been removed");
+}
+
+#[test]
+fn automatic_head_and_allow() {
+    expect_wrangler();
+
+    let response = Client::new()
+        .head("http://127.0.0.1:8787/closure")
+        .send()
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    assert_eq!(
+        response
+            .headers()
+            .get("content-type")
+            .map(|v| v.to_str().unwrap()),
+        Some("text/plain")
+    );
+    assert_eq!(response.text().unwrap(), "");
+
+    let response = Client::new()
+        .post("http://127.0.0.1:8787/closure")
+        .send()
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::METHOD_NOT_ALLOWED);
+    assert_eq!(
+        response.headers().get("allow").map(|v| v.to_str().unwrap()),
+        Some("HEAD, GET, OPTIONS")
+    );
+}
+
+#[test]
+fn cors_policy() {
+    let header = |response: &reqwest::blocking::Response, name: &str| {
+        response
+            .headers()
+            .get(name)
+            .map(|v| v.to_str().unwrap().to_string())
+    };
+
+    let response = get("cors-policy/hello", |r| {
+        r.header("Origin", "https://api.example.org")
+    });
+    assert_eq!(
+        header(&response, "access-control-allow-origin").as_deref(),
+        Some("https://api.example.org")
+    );
+    assert_eq!(
+        header(&response, "access-control-expose-headers").as_deref(),
+        Some("x-custom")
+    );
+    assert_eq!(header(&response, "vary").as_deref(), Some("Origin"));
+
+    let response = get("cors-policy/hello", |r| {
+        r.header("Origin", "https://example.net")
+    });
+    assert_eq!(header(&response, "access-control-allow-origin"), None);
+    assert_eq!(response.text().unwrap(), "hello from a cors policy");
+}
+
+#[test]
+fn cors_policy_error() {
+    expect_wrangler();
+
+    let response = reqwest::blocking::Client::new()
+        .get("http://127.0.0.1:8787/cors-policy/error")
+        .header("Origin", "https://example.com")
+        .send()
+        .unwrap();
+    assert_eq!(response.status(), 500);
+    assert_eq!(
+        response
+            .headers()
+            .get("access-control-allow-origin")
+            .map(|v| v.to_str().unwrap()),
+        Some("https://example.com")
+    );
+    assert_eq!(response.text().unwrap(), "handled error from a cors policy");
+}
+
 #[test]
 fn test_data() {
     let body = get("test-data", |r| r).text().unwrap();
@@ -143,6 +341,12 @@ fn durable_id() {
     assert!(body.starts_with("[durable_object]"));
 }
 
+#[test]
+fn durable_rpc() {
+    let body = get("durable/rpc", |r| r).text().unwrap();
+    assert_eq!(body, "underflow at 5");
+}
+
 #[test]
 fn durable_alarm() {
     let body = get("durable/alarm", |r| r).text().unwrap();
@@ -216,6 +420,27 @@ fn status_code() {
     assert_eq!(status_code, StatusCode::IM_A_TEAPOT);
 }
 
+#[test]
+fn middleware() {
+    let response = get("middleware/hello", |r| {
+        r.header("x-middleware-key", "open sesame")
+    });
+    let middleware_header = response
+        .headers()
+        .get("x-middleware")
+        .cloned()
+        .and_then(|x| x.to_str().ok().map(String::from))
+        .expect("no middleware header");
+
+    assert_eq!(middleware_header, "ran");
+    assert_eq!(response.text().unwrap(), "hello from behind middleware");
+
+    let status_code = reqwest::blocking::get("http://127.0.0.1:8787/middleware/hello")
+        .unwrap()
+        .status();
+    assert_eq!(status_code, StatusCode::UNAUTHORIZED);
+}
+
 #[test]
 fn root() {
     // Theres more routes with the exact same path and respond function, so we'll just cover them
@@ -5,7 +5,11 @@
 #![allow(unused)]
 
 use reqwest::Url;
-use tungstenite::{connect, Message};
+use tungstenite::{
+    connect,
+    protocol::{frame::coding::CloseCode, CloseFrame},
+    Message,
+};
 
 mod util;
 
@@ -39,3 +43,43 @@ fn websocket() {
         .expect("body was not boolean");
     assert!(got_close_event)
 }
+
+#[test]
+fn hibernating_durable_object_websocket() {
+    util::expect_wrangler();
+
+    let (mut socket, _) =
+        connect(Url::parse("ws://127.0.0.1:8787/durable/websocket/room").unwrap())
+            .expect("Can't connect");
+    let mut read_text = |socket: &mut tungstenite::WebSocket<_>| {
+        socket.read_message().unwrap().into_text().unwrap()
+    };
+
+    // echoed back by `websocket_message`
+    socket
+        .write_message(Message::Text("Hello, world!".into()))
+        .unwrap();
+    assert_eq!(read_text(&mut socket), "Hello, world!");
+
+    // answered by the runtime without waking the object
+    socket.write_message(Message::Text("ping".into())).unwrap();
+    assert_eq!(read_text(&mut socket), "pong");
+
+    // closed back by `websocket_close` with the same code and reason
+    socket
+        .close(Some(CloseFrame {
+            code: CloseCode::Normal,
+            reason: "bye".into(),
+        }))
+        .unwrap();
+    let frame = loop {
+        match socket.read_message() {
+            Ok(Message::Close(frame)) => break frame,
+            Ok(_) => continue,
+            Err(e) => panic!("socket closed without a close frame: {}", e),
+        }
+    };
+    let frame = frame.expect("close frame has no code");
+    assert_eq!(frame.code, CloseCode::Normal);
+    assert_eq!(frame.reason, "bye");
+}
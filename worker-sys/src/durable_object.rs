@@ -1,6 +1,9 @@
 use std::result::Result as StdResult;
 
-use crate::Request as EdgeRequest;
+use crate::{
+    websocket::{WebSocket, WebSocketRequestResponsePair},
+    Request as EdgeRequest, RequestInit,
+};
 
 use js_sys::JsString;
 use wasm_bindgen::{closure::Closure, prelude::*};
@@ -13,6 +16,12 @@ extern "C" {
     #[wasm_bindgen(method, js_class = "JsObjectId", js_name = toString)]
     pub fn to_string(this: &JsObjectId) -> JsString;
 
+    #[wasm_bindgen(method, js_class = "JsObjectId", js_name = equals)]
+    pub fn equals(this: &JsObjectId, other: &JsObjectId) -> bool;
+
+    #[wasm_bindgen(method, getter, js_class = "JsObjectId", js_name = name)]
+    pub fn name(this: &JsObjectId) -> Option<String>;
+
     #[wasm_bindgen (extends = ::js_sys::Object, js_name = DurableObject)]
     pub type ObjectStub;
 
@@ -20,6 +29,7 @@ extern "C" {
     pub type ObjectNamespace;
 
     #[wasm_bindgen (extends = ::js_sys::Object, js_name = DurableObjectState)]
+    #[derive(Clone)]
     pub type ObjectState;
 
     #[wasm_bindgen(method, getter, js_class = "DurableObjectState", js_name = id)]
@@ -28,6 +38,45 @@ extern "C" {
     #[wasm_bindgen(method, getter, js_class = "DurableObjectState", js_name = storage)]
     pub fn storage_internal(this: &ObjectState) -> ObjectStorage;
 
+    #[wasm_bindgen(catch, method, js_class = "DurableObjectState", js_name = blockConcurrencyWhile)]
+    pub fn block_concurrency_while_internal(
+        this: &ObjectState,
+        callback: &Closure<dyn FnMut() -> ::js_sys::Promise>,
+    ) -> StdResult<::js_sys::Promise, JsValue>;
+
+    #[wasm_bindgen(method, js_class = "DurableObjectState", js_name = waitUntil)]
+    pub fn wait_until_internal(this: &ObjectState, promise: &::js_sys::Promise);
+
+    #[wasm_bindgen(catch, method, js_class = "DurableObjectState", js_name = acceptWebSocket)]
+    pub fn accept_websocket_internal(
+        this: &ObjectState,
+        ws: &WebSocket,
+        tags: ::js_sys::Array,
+    ) -> StdResult<(), JsValue>;
+
+    #[wasm_bindgen(catch, method, js_class = "DurableObjectState", js_name = getWebSockets)]
+    pub fn get_websockets_internal(
+        this: &ObjectState,
+        tag: Option<&str>,
+    ) -> StdResult<::js_sys::Array, JsValue>;
+
+    #[wasm_bindgen(catch, method, js_class = "DurableObjectState", js_name = setWebSocketAutoResponse)]
+    pub fn set_websocket_auto_response_internal(
+        this: &ObjectState,
+        pair: Option<&WebSocketRequestResponsePair>,
+    ) -> StdResult<(), JsValue>;
+
+    #[wasm_bindgen(method, js_class = "DurableObjectState", js_name = getWebSocketAutoResponse)]
+    pub fn get_websocket_auto_response_internal(
+        this: &ObjectState,
+    ) -> Option<WebSocketRequestResponsePair>;
+
+    #[wasm_bindgen(method, js_class = "DurableObjectState", js_name = getWebSocketAutoResponseTimestamp)]
+    pub fn get_websocket_auto_response_timestamp_internal(
+        this: &ObjectState,
+        ws: &WebSocket,
+    ) -> Option<::js_sys::Date>;
+
     #[wasm_bindgen (catch, method, js_class = "DurableObjectNamespace", js_name = idFromName)]
     pub fn id_from_name_internal(
         this: &ObjectNamespace,
@@ -52,25 +101,51 @@ extern "C" {
     #[wasm_bindgen (catch, method, js_class = "DurableObjectNamespace", js_name = get)]
     pub fn get_internal(this: &ObjectNamespace, id: &JsObjectId) -> StdResult<ObjectStub, JsValue>;
 
+    #[wasm_bindgen (catch, method, js_class = "DurableObjectNamespace", js_name = get)]
+    pub fn get_with_options_internal(
+        this: &ObjectNamespace,
+        id: &JsObjectId,
+        options: &JsValue,
+    ) -> StdResult<ObjectStub, JsValue>;
+
+    #[wasm_bindgen (catch, method, js_class = "DurableObjectNamespace", js_name = jurisdiction)]
+    pub fn jurisdiction_internal(
+        this: &ObjectNamespace,
+        jurisdiction: &str,
+    ) -> StdResult<ObjectNamespace, JsValue>;
+
     #[wasm_bindgen (method, js_class = "DurableObject", js_name = fetch)]
     pub fn fetch_with_request_internal(this: &ObjectStub, req: &EdgeRequest) -> ::js_sys::Promise;
 
     #[wasm_bindgen (method, js_class = "DurableObject", js_name = fetch)]
     pub fn fetch_with_str_internal(this: &ObjectStub, url: &str) -> ::js_sys::Promise;
+
+    #[wasm_bindgen (method, js_class = "DurableObject", js_name = fetch)]
+    pub fn fetch_with_str_and_init_internal(
+        this: &ObjectStub,
+        url: &str,
+        init: &RequestInit,
+    ) -> ::js_sys::Promise;
 }
 
 #[wasm_bindgen]
 extern "C" {
     #[wasm_bindgen (extends = ::js_sys::Object, js_name = DurableObjectStorage)]
+    #[derive(Clone)]
     pub type ObjectStorage;
 
     #[wasm_bindgen(catch, method, js_class = "DurableObjectStorage", js_name = get)]
-    pub fn get_internal(this: &ObjectStorage, key: &str) -> StdResult<::js_sys::Promise, JsValue>;
+    pub fn get_internal(
+        this: &ObjectStorage,
+        key: &str,
+        options: JsValue,
+    ) -> StdResult<::js_sys::Promise, JsValue>;
 
     #[wasm_bindgen(catch, method, js_class = "DurableObjectStorage", js_name = get)]
     pub fn get_multiple_internal(
         this: &ObjectStorage,
         keys: Vec<JsValue>,
+        options: JsValue,
     ) -> StdResult<::js_sys::Promise, JsValue>;
 
     #[wasm_bindgen(catch, method, js_class = "DurableObjectStorage", js_name = put)]
@@ -78,24 +153,28 @@ extern "C" {
         this: &ObjectStorage,
         key: &str,
         value: JsValue,
+        options: JsValue,
     ) -> StdResult<::js_sys::Promise, JsValue>;
 
     #[wasm_bindgen(catch, method, js_class = "DurableObjectStorage", js_name = put)]
     pub fn put_multiple_internal(
         this: &ObjectStorage,
         value: JsValue,
+        options: JsValue,
     ) -> StdResult<::js_sys::Promise, JsValue>;
 
     #[wasm_bindgen(catch, method, js_class = "DurableObjectStorage", js_name = delete)]
     pub fn delete_internal(
         this: &ObjectStorage,
         key: &str,
+        options: JsValue,
     ) -> StdResult<::js_sys::Promise, JsValue>;
 
     #[wasm_bindgen(catch, method, js_class = "DurableObjectStorage", js_name = delete)]
     pub fn delete_multiple_internal(
         this: &ObjectStorage,
         keys: Vec<JsValue>,
+        options: JsValue,
     ) -> StdResult<::js_sys::Promise, JsValue>;
 
     #[wasm_bindgen(catch, method, js_class = "DurableObjectStorage", js_name = deleteAll)]
@@ -110,10 +189,13 @@ extern "C" {
         options: ::js_sys::Object,
     ) -> StdResult<::js_sys::Promise, JsValue>;
 
+    #[wasm_bindgen(catch, method, js_class = "DurableObjectStorage", js_name = sync)]
+    pub fn sync_internal(this: &ObjectStorage) -> StdResult<::js_sys::Promise, JsValue>;
+
     #[wasm_bindgen(catch, method, js_class = "DurableObjectStorage", js_name = transaction)]
     pub fn transaction_internal(
         this: &ObjectStorage,
-        closure: &Closure<dyn FnMut(ObjectTransaction)>,
+        closure: &Closure<dyn FnMut(ObjectTransaction) -> ::js_sys::Promise>,
     ) -> StdResult<::js_sys::Promise, JsValue>;
 
     #[wasm_bindgen(catch, method, js_class = "DurableObjectStorage", js_name = getAlarm)]
@@ -139,6 +221,7 @@ extern "C" {
 #[wasm_bindgen]
 extern "C" {
     #[wasm_bindgen(extends = ::js_sys::Object, js_name = DurableObjectTransaction)]
+    #[derive(Clone)]
     pub type ObjectTransaction;
 
     #[wasm_bindgen(catch, method, js_class = "DurableObjectTransaction", js_name = get)]
@@ -77,6 +77,18 @@ extern "C" {
     #[doc = "[MDN Documentation](https://developer.mozilla.org/en-US/docs/Web/API/WebSocket/send)"]
     pub fn send_with_u8_array(this: &WebSocket, data: Uint8Array) -> Result<(), JsValue>;
 
+    #[wasm_bindgen(catch, method, structural, js_class = "WebSocket", js_name = serializeAttachment)]
+    #[doc = "Stores a value with the WebSocket which is kept while its Durable Object hibernates."]
+    #[doc = ""]
+    #[doc = "[CF Documentation](https://developers.cloudflare.com/durable-objects/api/websockets/#serializeattachment)"]
+    pub fn serialize_attachment(this: &WebSocket, value: JsValue) -> Result<(), JsValue>;
+
+    #[wasm_bindgen(catch, method, structural, js_class = "WebSocket", js_name = deserializeAttachment)]
+    #[doc = "Retrieves the value stored with `serializeAttachment()`."]
+    #[doc = ""]
+    #[doc = "[CF Documentation](https://developers.cloudflare.com/durable-objects/api/websockets/#deserializeattachment)"]
+    pub fn deserialize_attachment(this: &WebSocket) -> Result<JsValue, JsValue>;
+
     #[wasm_bindgen(catch, method, structural, js_class = "WebSocket", js_name = addEventListener)]
     #[doc = "The `addEventListener()` method."]
     #[doc = ""]
@@ -97,3 +109,20 @@ extern "C" {
         value: Option<&::js_sys::Function>,
     ) -> Result<(), JsValue>;
 }
+
+#[wasm_bindgen]
+extern "C" {
+    #[wasm_bindgen(extends = js_sys::Object, js_name = WebSocketRequestResponsePair)]
+    #[derive(Debug, Clone, PartialEq, Eq)]
+    #[doc = "A request and the response sent for it automatically by a hibernatable WebSocket."]
+    pub type WebSocketRequestResponsePair;
+
+    #[wasm_bindgen(catch, constructor, js_class = WebSocketRequestResponsePair)]
+    pub fn new(request: &str, response: &str) -> Result<WebSocketRequestResponsePair, JsValue>;
+
+    #[wasm_bindgen(method, getter, js_class = "WebSocketRequestResponsePair")]
+    pub fn request(this: &WebSocketRequestResponsePair) -> String;
+
+    #[wasm_bindgen(method, getter, js_class = "WebSocketRequestResponsePair")]
+    pub fn response(this: &WebSocketRequestResponsePair) -> String;
+}
@@ -1,16 +1,50 @@
-use crate::{Error, Headers, Method, Result};
+use std::{fmt, sync::Arc};
+
+use crate::{Error, Headers, Method, Request, Response, Result};
+
+type OriginFn = Arc<dyn Fn(&str) -> bool + Send + Sync>;
+
+/// Methods which browsers allow in cross-origin requests without them being listed in the
+/// `Access-Control-Allow-Methods` header of a preflight response.
+const SAFELISTED_METHODS: [Method; 3] = [Method::Get, Method::Head, Method::Post];
 
 /// Cors struct, holding cors configuration
-#[derive(Debug, Clone)]
+///
+/// Besides writing the configured headers with `apply_headers`, the configuration can be used as a
+/// policy: requests are checked against the allowed origins, methods and headers, preflight
+/// requests are answered with [`Cors::preflight`], and actual responses are decorated with
+/// [`Cors::apply_to`]. Register it with [`Router::cors`](crate::Router::cors) to do both for every
+/// route of a `Router`.
+///
+/// Allowed origins may be exact (`https://example.com`), match any subdomain
+/// (`https://*.example.com`) or be `*` to allow any origin. Use `with_origin_fn` to match origins
+/// with a regular expression or any other logic. Since browsers don't send credentials to any
+/// origin, `*` is ignored by a configuration allowing credentials.
+#[derive(Clone)]
 pub struct Cors {
     credentials: bool,
     max_age: Option<u32>,
     origins: Vec<String>,
+    origin_fn: Option<OriginFn>,
     methods: Vec<Method>,
     allowed_headers: Vec<String>,
     exposed_headers: Vec<String>,
 }
 
+impl fmt::Debug for Cors {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Cors")
+            .field("credentials", &self.credentials)
+            .field("max_age", &self.max_age)
+            .field("origins", &self.origins)
+            .field("origin_fn", &self.origin_fn.as_ref().map(|_| ".."))
+            .field("methods", &self.methods)
+            .field("allowed_headers", &self.allowed_headers)
+            .field("exposed_headers", &self.exposed_headers)
+            .finish()
+    }
+}
+
 /// Creates a default cors configuration, which will do nothing.
 impl Default for Cors {
     fn default() -> Self {
@@ -18,6 +52,7 @@ impl Default for Cors {
             credentials: false,
             max_age: None,
             origins: vec![],
+            origin_fn: None,
             methods: vec![],
             allowed_headers: vec![],
             exposed_headers: vec![],
@@ -31,7 +66,8 @@ impl Cors {
         Self::default()
     }
 
-    /// Configures whether cors is allowed to share credentials or not.
+    /// Configures whether cors is allowed to share credentials or not. Origins have to be allowed
+    /// explicitly to share credentials with them, as `*` no longer allows any origin.
     pub fn with_credentials(mut self, credentials: bool) -> Self {
         self.credentials = credentials;
         self
@@ -52,6 +88,16 @@ impl Cors {
         self
     }
 
+    /// Configures a function deciding whether an origin is allowed for cors, in addition to the
+    /// origins configured with `with_origins`, e.g. to match origins with a regular expression.
+    pub fn with_origin_fn(
+        mut self,
+        origin_fn: impl Fn(&str) -> bool + Send + Sync + 'static,
+    ) -> Self {
+        self.origin_fn = Some(Arc::new(origin_fn));
+        self
+    }
+
     /// Configures which methods are allowed for cors.
     pub fn with_methods<V: IntoIterator<Item = Method>>(mut self, methods: V) -> Self {
         self.methods = methods.into_iter().collect();
@@ -116,6 +162,196 @@ impl Cors {
         }
         Ok(())
     }
+
+    /// Whether the origin is allowed for cors.
+    pub fn allows_origin(&self, origin: &str) -> bool {
+        let matches = |allowed: &String| {
+            if allowed == "*" {
+                // credentials must never be shared with whichever origin asks for them
+                return !self.credentials;
+            }
+            if allowed.eq_ignore_ascii_case(origin) {
+                return true;
+            }
+
+            // `https://*.example.com` matches any subdomain of `example.com`
+            match allowed.split_once("*.") {
+                Some((scheme, domain)) => {
+                    let origin = origin.to_ascii_lowercase();
+                    let scheme = scheme.to_ascii_lowercase();
+                    let suffix = format!(".{}", domain.to_ascii_lowercase());
+                    // every label of the subdomain has to be non-empty
+                    match origin
+                        .strip_prefix(scheme.as_str())
+                        .and_then(|rest| rest.strip_suffix(suffix.as_str()))
+                    {
+                        Some(subdomain) => subdomain.split('.').all(|label| !label.is_empty()),
+                        None => false,
+                    }
+                }
+                None => false,
+            }
+        };
+
+        if self.origins.iter().any(matches) {
+            return true;
+        }
+        match &self.origin_fn {
+            Some(origin_fn) => origin_fn(origin),
+            None => false,
+        }
+    }
+
+    /// Whether the method is allowed for cors. The CORS-safelisted GET, HEAD and POST methods are
+    /// always allowed.
+    pub fn allows_method(&self, method: &Method) -> bool {
+        SAFELISTED_METHODS.contains(method) || self.methods.contains(method)
+    }
+
+    /// Whether all of the request headers are allowed for cors. Any header is allowed if the
+    /// allowed headers include `*`.
+    pub fn allows_headers<S: AsRef<str>>(&self, headers: &[S]) -> bool {
+        self.allows_any_header()
+            || headers.iter().all(|header| {
+                self.allowed_headers
+                    .iter()
+                    .any(|allowed| allowed.eq_ignore_ascii_case(header.as_ref().trim()))
+            })
+    }
+
+    /// Whether the request is a CORS preflight request, i.e. an OPTIONS request with both an
+    /// `Origin` and an `Access-Control-Request-Method` header.
+    pub fn is_preflight(req: &Request) -> Result<bool> {
+        Ok(req.method() == Method::Options
+            && req.headers().has("Origin")?
+            && req.headers().has("Access-Control-Request-Method")?)
+    }
+
+    /// Answer a CORS preflight request. If the origin, the requested method and all of the
+    /// requested headers are allowed, responds with `204 No Content` and the headers granting
+    /// access, otherwise responds with `403 Forbidden`.
+    pub fn preflight(&self, req: &Request) -> Result<Response> {
+        let headers = req.headers();
+        let origin = headers.get("Origin")?.unwrap_or_default();
+        let method = headers
+            .get("Access-Control-Request-Method")?
+            .unwrap_or_default();
+        let request_headers: Vec<String> = headers
+            .get("Access-Control-Request-Headers")?
+            .map(|value| {
+                value
+                    .split(',')
+                    .map(|header| header.trim().to_string())
+                    .filter(|header| !header.is_empty())
+                    .collect()
+            })
+            .unwrap_or_default();
+
+        let allowed = self.allows_origin(&origin)
+            && self.allows_requested_method(&method)
+            && self.allows_headers(&request_headers);
+        let mut resp = if allowed {
+            Response::empty()?.with_status(204)
+        } else {
+            Response::error("Forbidden", 403)?
+        };
+
+        let resp_headers = resp.headers_mut();
+        resp_headers.append(
+            "Vary",
+            "Origin, Access-Control-Request-Method, Access-Control-Request-Headers",
+        )?;
+        if !allowed {
+            return Ok(resp);
+        }
+
+        self.set_allow_origin(resp_headers, &origin)?;
+        let methods = if self.methods.is_empty() {
+            concat_vec_to_string(&SAFELISTED_METHODS)?
+        } else {
+            concat_vec_to_string(self.methods.as_slice())?
+        };
+        resp_headers.set("Access-Control-Allow-Methods", &methods)?;
+        if !request_headers.is_empty() {
+            let allowed_headers = if self.allows_any_header() {
+                concat_vec_to_string(request_headers.as_slice())?
+            } else {
+                concat_vec_to_string(self.allowed_headers.as_slice())?
+            };
+            resp_headers.set("Access-Control-Allow-Headers", &allowed_headers)?;
+        }
+        if let Some(max_age) = self.max_age {
+            resp_headers.set("Access-Control-Max-Age", &max_age.to_string())?;
+        }
+
+        Ok(resp)
+    }
+
+    /// Decorate the response to an actual (non-preflight) request, granting access to it if the
+    /// request's origin is allowed.
+    pub fn apply_to(&self, req: &Request, resp: &mut Response) -> Result<()> {
+        let origin = req.headers().get("Origin")?;
+        self.apply_to_origin(origin.as_deref(), resp.headers_mut())
+    }
+
+    pub(crate) fn apply_to_origin(
+        &self,
+        origin: Option<&str>,
+        headers: &mut Headers,
+    ) -> Result<()> {
+        // the response varies by origin unless every origin is granted access the same way
+        if !self.allows_every_origin() {
+            headers.append("Vary", "Origin")?;
+        }
+
+        let origin = match origin {
+            Some(origin) if self.allows_origin(origin) => origin,
+            _ => return Ok(()),
+        };
+
+        self.set_allow_origin(headers, origin)?;
+        if !self.exposed_headers.is_empty() {
+            headers.set(
+                "Access-Control-Expose-Headers",
+                concat_vec_to_string(self.exposed_headers.as_slice())?.as_str(),
+            )?;
+        }
+        Ok(())
+    }
+
+    fn set_allow_origin(&self, headers: &mut Headers, origin: &str) -> Result<()> {
+        let origin = if self.allows_every_origin() {
+            "*"
+        } else {
+            origin
+        };
+        headers.set("Access-Control-Allow-Origin", origin)?;
+        if self.credentials {
+            headers.set("Access-Control-Allow-Credentials", "true")?;
+        }
+        Ok(())
+    }
+
+    /// Whether `*` can be sent as the allowed origin, which browsers reject for credentialed
+    /// requests.
+    fn allows_every_origin(&self) -> bool {
+        !self.credentials && self.origins.iter().any(|origin| origin == "*")
+    }
+
+    /// Whether the method named in a preflight request is allowed. Unlike `Method::from`, methods
+    /// which aren't recognised aren't taken for GET, and are never allowed.
+    // `Option::is_some_and` would need Rust 1.70
+    #[allow(clippy::unnecessary_map_or)]
+    fn allows_requested_method(&self, name: &str) -> bool {
+        Method::all()
+            .into_iter()
+            .find(|method| method.as_ref().eq_ignore_ascii_case(name.trim()))
+            .map_or(false, |method| self.allows_method(&method))
+    }
+
+    fn allows_any_header(&self) -> bool {
+        self.allowed_headers.iter().any(|header| header == "*")
+    }
 }
 
 fn concat_vec_to_string<S: AsRef<str>>(vec: &[S]) -> Result<String> {
@@ -132,3 +368,62 @@ fn concat_vec_to_string<S: AsRef<str>>(vec: &[S]) -> Result<String> {
         ))
     }
 }
+
+#[test]
+fn cors_allows_origins() {
+    let cors = Cors::new()
+        .with_origins(["https://example.com", "https://*.example.org"])
+        .with_origin_fn(|origin| origin.ends_with(".pages.dev"));
+
+    assert!(cors.allows_origin("https://example.com"));
+    assert!(cors.allows_origin("https://EXAMPLE.com"));
+    assert!(!cors.allows_origin("https://api.example.com"));
+    assert!(cors.allows_origin("https://api.example.org"));
+    assert!(cors.allows_origin("https://a.b.example.org"));
+    assert!(!cors.allows_origin("https://example.org"));
+    assert!(!cors.allows_origin("http://api.example.org"));
+    assert!(!cors.allows_origin("https://evilexample.org"));
+    assert!(!cors.allows_origin("https://.example.org"));
+    assert!(!cors.allows_origin("https://a..example.org"));
+    assert!(cors.allows_origin("https://preview.pages.dev"));
+    assert!(!cors.allows_origin("https://example.net"));
+
+    assert!(Cors::new()
+        .with_origins(["*"])
+        .allows_origin("https://example.net"));
+    assert!(!Cors::new().allows_origin("https://example.net"));
+}
+
+#[test]
+fn cors_never_shares_credentials_with_any_origin() {
+    let cors = Cors::new()
+        .with_origins(["*", "https://example.com"])
+        .with_credentials(true);
+
+    assert!(!cors.allows_origin("https://example.net"));
+    assert!(cors.allows_origin("https://example.com"));
+    assert!(!cors.allows_every_origin());
+}
+
+#[test]
+fn cors_allows_methods_and_headers() {
+    let cors = Cors::new()
+        .with_methods([Method::Put])
+        .with_allowed_headers(["Content-Type", "X-Api-Key"]);
+
+    assert!(cors.allows_method(&Method::Get));
+    assert!(cors.allows_method(&Method::Put));
+    assert!(!cors.allows_method(&Method::Delete));
+    assert!(cors.allows_requested_method("PUT"));
+    assert!(cors.allows_requested_method("get"));
+    assert!(!cors.allows_requested_method("DELETE"));
+    assert!(!cors.allows_requested_method("PURGE"));
+    assert!(!cors.allows_requested_method(""));
+
+    assert!(cors.allows_headers(&["content-type", " x-api-key"]));
+    assert!(!cors.allows_headers(&["content-type", "authorization"]));
+    assert!(cors.allows_headers::<&str>(&[]));
+    assert!(Cors::new()
+        .with_allowed_headers(["*"])
+        .allows_headers(&["authorization"]));
+}
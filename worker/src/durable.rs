@@ -10,31 +10,44 @@
 //! [Learn more](https://developers.cloudflare.com/workers/learning/using-durable-objects) about
 //! using Durable Objects.
 
-use std::{ops::Deref, time::Duration};
+use std::{
+    cell::RefCell, collections::HashMap, convert::TryFrom, future::Future, ops::Deref, rc::Rc,
+    time::Duration,
+};
 
 use crate::{
     date::Date,
     env::{Env, EnvBinding},
     error::Error,
     request::Request,
+    request_init::RequestInit,
     response::Response,
+    websocket::WebSocket,
     Result,
 };
 
 use async_trait::async_trait;
 use chrono::{DateTime, Utc};
-use js_sys::{Map, Number, Object};
-use serde::{de::DeserializeOwned, Serialize};
+use js_sys::{Array, Map, Number, Object};
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
 use wasm_bindgen::{prelude::*, JsCast};
+use wasm_bindgen_futures::{future_to_promise, JsFuture};
 use worker_sys::{
     durable_object::{
         JsObjectId, ObjectNamespace as EdgeObjectNamespace, ObjectState, ObjectStorage, ObjectStub,
         ObjectTransaction,
     },
-    Response as EdgeResponse,
+    Response as EdgeResponse, WebSocketRequestResponsePair as EdgeWebSocketRequestResponsePair,
 };
-// use wasm_bindgen_futures::future_to_promise;
-use wasm_bindgen_futures::JsFuture;
+
+mod key;
+mod persisted;
+mod rpc;
+mod typed;
+
+pub use persisted::{Persisted, PersistedMut};
+pub use rpc::{is_rpc_request, RpcCall, RpcError};
+pub use typed::TypedStorage;
 
 /// A Durable Object stub is a client object used to send requests to a remote Durable Object.
 pub struct Stub {
@@ -55,6 +68,16 @@ impl Stub {
         let response = JsFuture::from(promise).await?;
         Ok(response.dyn_into::<EdgeResponse>()?.into())
     }
+
+    /// Construct a Request from a URL and the method, headers and body in `init`, and send it to
+    /// the Durable Object to which the stub points.
+    pub async fn fetch_with_init(&self, url: &str, init: &RequestInit) -> Result<Response> {
+        let promise = self
+            .inner
+            .fetch_with_str_and_init_internal(url, &init.into());
+        let response = JsFuture::from(promise).await?;
+        Ok(response.dyn_into::<EdgeResponse>()?.into())
+    }
 }
 
 /// Use an ObjectNamespace to get access to Stubs for communication with a Durable Object instance.
@@ -77,6 +100,12 @@ impl ObjectNamespace {
             })
     }
 
+    /// Get a Stub for the Durable Object with the given name, i.e. the one identified by
+    /// `id_from_name(name)`.
+    pub fn get_by_name(&self, name: &str) -> Result<Stub> {
+        self.id_from_name(name)?.get_stub()
+    }
+
     /// This method parses an ID that was previously stringified. This is useful in particular with
     /// IDs created using `unique_id(&self)`, as these IDs need to be stored somewhere, probably as
     // as a string.
@@ -128,6 +157,14 @@ impl ObjectNamespace {
                 namespace: Some(self),
             })
     }
+
+    /// A view of this namespace whose ids, including those created by `id_from_name()`, are
+    /// restricted to the given jurisdiction, e.g. `"eu"`.
+    pub fn jurisdiction(&self, jd: &str) -> Result<ObjectNamespace> {
+        Ok(ObjectNamespace {
+            inner: self.inner.jurisdiction_internal(jd)?,
+        })
+    }
 }
 
 /// An ObjectId is used to identify, locate, and access a Durable Object via interaction with its
@@ -149,6 +186,47 @@ impl ObjectId<'_> {
             })
             .map_err(Error::from)
     }
+
+    /// Get a Stub for the Durable Object instance identified by this ObjectId, with options such
+    /// as where the object should be created if it doesn't exist yet.
+    pub fn get_stub_with_options(&self, options: StubOptions) -> Result<Stub> {
+        let options = serde_wasm_bindgen::to_value(&options)?;
+        self.namespace
+            .ok_or_else(|| JsValue::from("Cannot get stub from within a Durable Object"))
+            .and_then(|n| {
+                Ok(Stub {
+                    inner: n.inner.get_with_options_internal(&self.inner, &options)?,
+                })
+            })
+            .map_err(Error::from)
+    }
+
+    /// The name this ObjectId was derived from with `id_from_name()`, if it is known.
+    pub fn name(&self) -> Option<String> {
+        self.inner.name()
+    }
+
+    /// Whether this ObjectId identifies the same Durable Object as `other`.
+    pub fn equals(&self, other: &ObjectId) -> bool {
+        self.inner.equals(&other.inner)
+    }
+}
+
+impl PartialEq for ObjectId<'_> {
+    fn eq(&self, other: &Self) -> bool {
+        self.equals(other)
+    }
+}
+
+/// Options for [`ObjectId::get_stub_with_options`].
+#[derive(Debug, Clone, Default, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct StubOptions {
+    /// Where a Durable Object which doesn't exist yet should preferably be created, e.g. `"weur"`
+    /// for Western Europe. See the supported locations at:
+    /// <https://developers.cloudflare.com/durable-objects/reference/data-location/#provide-a-location-hint>
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub location_hint: Option<String>,
 }
 
 impl ToString for ObjectId<'_> {
@@ -181,6 +259,128 @@ impl State {
         }
     }
 
+    /// Runs `callback` while no other events, such as requests, are delivered to this Durable
+    /// Object, and returns its result. This is useful for initializing the object from storage
+    /// without racing the requests which arrive in the meantime.
+    ///
+    /// If the callback fails, the object is reset, since its in-memory state can no longer be
+    /// trusted, and its error is returned.
+    ///
+    /// ```no_run
+    /// # use worker::*;
+    /// # async fn example(state: State) -> Result<()> {
+    /// let storage = state.storage();
+    /// let count: usize = state
+    ///     .block_concurrency_while(move || async move { storage.get_or_default("count").await })
+    ///     .await?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub async fn block_concurrency_while<F, Fut, T>(&self, callback: F) -> Result<T>
+    where
+        F: FnOnce() -> Fut + 'static,
+        Fut: Future<Output = Result<T>> + 'static,
+        T: 'static,
+    {
+        // the callback's result can't always be passed through JS, so it's handed back through here
+        let result = Rc::new(RefCell::new(None));
+        let callback_result = result.clone();
+
+        let callback = Closure::once(move || {
+            future_to_promise(async move {
+                let outcome = callback().await;
+                let failed = outcome.as_ref().err().map(|e| JsValue::from(e.to_string()));
+                *callback_result.borrow_mut() = Some(outcome);
+
+                match failed {
+                    Some(e) => Err(e),
+                    None => Ok(JsValue::UNDEFINED),
+                }
+            })
+        });
+
+        let done = JsFuture::from(self.inner.block_concurrency_while_internal(&callback)?).await;
+        let outcome = result.borrow_mut().take();
+        match outcome {
+            Some(outcome) => outcome,
+            None => Err(done
+                .err()
+                .map(Error::from)
+                .unwrap_or_else(|| "blockConcurrencyWhile callback was never called".into())),
+        }
+    }
+
+    /// Extends the lifetime of this Durable Object until the given future has completed, e.g. for
+    /// background work which shouldn't delay the response.
+    pub fn wait_until<F>(&self, future: F)
+    where
+        F: Future<Output = ()> + 'static,
+    {
+        self.inner.wait_until_internal(&future_to_promise(async {
+            future.await;
+            Ok(JsValue::UNDEFINED)
+        }))
+    }
+
+    /// A value persisted under the given key of this Durable Object's storage, which is loaded on
+    /// first access and written back with `flush`, or in the background once dropped. See
+    /// [`Persisted`].
+    pub fn persisted<T>(&self, key: &str) -> Persisted<T>
+    where
+        T: Serialize + DeserializeOwned + Default,
+    {
+        Persisted::new(self, key.to_string())
+    }
+
+    /// Accepts the server side of a `WebSocketPair` using the hibernation API: instead of the
+    /// Durable Object listening for events, the runtime calls its `websocket_message`,
+    /// `websocket_close` and `websocket_error` methods, which lets the Object be evicted from
+    /// memory while the connection stays open. The tags can be used to find the WebSocket again
+    /// with `get_web_sockets`.
+    pub fn accept_web_socket(&self, ws: &WebSocket, tags: &[&str]) -> Result<()> {
+        let tags = tags.iter().copied().map(JsValue::from).collect();
+        self.inner
+            .accept_websocket_internal(ws.as_ref(), tags)
+            .map_err(Error::from)
+    }
+
+    /// Gets the WebSockets accepted with `accept_web_socket`, or only those with the given tag.
+    pub fn get_web_sockets(&self, tag: Option<&str>) -> Result<Vec<WebSocket>> {
+        Ok(self
+            .inner
+            .get_websockets_internal(tag)?
+            .iter()
+            .map(|ws| WebSocket::from(ws.unchecked_into::<worker_sys::WebSocket>()))
+            .collect())
+    }
+
+    /// Sets a request which the runtime answers with a response on every WebSocket accepted with
+    /// `accept_web_socket`, without waking the Durable Object, e.g. for keepalive pings. `None`
+    /// removes it.
+    pub fn set_web_socket_auto_response(
+        &self,
+        pair: Option<&WebSocketRequestResponsePair>,
+    ) -> Result<()> {
+        self.inner
+            .set_websocket_auto_response_internal(pair.map(|pair| &pair.inner))
+            .map_err(Error::from)
+    }
+
+    /// Gets the request and response set with `set_web_socket_auto_response`, if any.
+    pub fn get_web_socket_auto_response(&self) -> Option<WebSocketRequestResponsePair> {
+        self.inner
+            .get_websocket_auto_response_internal()
+            .map(|inner| WebSocketRequestResponsePair { inner })
+    }
+
+    /// Gets the time at which the runtime last answered the WebSocket with the auto response, if
+    /// it ever did.
+    pub fn get_web_socket_auto_response_timestamp(&self, ws: &WebSocket) -> Option<Date> {
+        self.inner
+            .get_websocket_auto_response_timestamp_internal(ws.as_ref())
+            .map(Date::from)
+    }
+
     // needs to be accessed by the `durable_object` macro in a conversion step
     pub fn _inner(self) -> ObjectState {
         self.inner
@@ -193,6 +393,48 @@ impl From<ObjectState> for State {
     }
 }
 
+/// A request and the response the runtime sends for it automatically on a hibernatable WebSocket,
+/// set with [`State::set_web_socket_auto_response`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct WebSocketRequestResponsePair {
+    inner: EdgeWebSocketRequestResponsePair,
+}
+
+impl WebSocketRequestResponsePair {
+    /// Create a pair answering messages equal to `request` with `response`.
+    pub fn new(request: &str, response: &str) -> Result<Self> {
+        Ok(Self {
+            inner: EdgeWebSocketRequestResponsePair::new(request, response)?,
+        })
+    }
+
+    /// The message which is answered automatically.
+    pub fn request(&self) -> String {
+        self.inner.request()
+    }
+
+    /// The message sent in response.
+    pub fn response(&self) -> String {
+        self.inner.response()
+    }
+}
+
+/// A message received by a WebSocket accepted with [`State::accept_web_socket`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum WebSocketIncomingMessage {
+    String(String),
+    Binary(Vec<u8>),
+}
+
+impl From<JsValue> for WebSocketIncomingMessage {
+    fn from(message: JsValue) -> Self {
+        match message.as_string() {
+            Some(message) => WebSocketIncomingMessage::String(message),
+            None => WebSocketIncomingMessage::Binary(js_sys::Uint8Array::new(&message).to_vec()),
+        }
+    }
+}
+
 /// Access a Durable Object's Storage API. Each method is implicitly wrapped inside a transaction,
 /// such that its results are atomic and isolated from all other storage operations, even when
 /// accessing multiple key-value pairs.
@@ -201,38 +443,81 @@ pub struct Storage {
 }
 
 impl Storage {
-    /// Retrieves the value associated with the given key. The type of the returned value will be
-    /// whatever was previously written for the key, or undefined if the key does not exist.
-    pub async fn get<T: serde::de::DeserializeOwned>(&self, key: &str) -> Result<T> {
-        JsFuture::from(self.inner.get_internal(key)?)
+    /// Retrieves the value associated with the given key, or `None` if the key does not exist.
+    pub async fn get<T: DeserializeOwned>(&self, key: &str) -> Result<Option<T>> {
+        self.get_with_options(key, GetOptions::default()).await
+    }
+
+    /// Retrieves the value associated with the given key, or `None` if the key does not exist.
+    pub async fn get_with_options<T: DeserializeOwned>(
+        &self,
+        key: &str,
+        options: GetOptions,
+    ) -> Result<Option<T>> {
+        let options = serde_wasm_bindgen::to_value(&options)?;
+        let val = JsFuture::from(self.inner.get_internal(key, options)?).await?;
+        from_stored_value(val)
+    }
+
+    /// Retrieves the value associated with the given key, or `T::default()` if the key does not
+    /// exist.
+    pub async fn get_or_default<T: DeserializeOwned + Default>(&self, key: &str) -> Result<T> {
+        self.get(key).await.map(Option::unwrap_or_default)
+    }
+
+    /// Retrieves the value associated with the given key, failing with `Error::KeyNotFound` if the
+    /// key does not exist.
+    pub async fn get_required<T: DeserializeOwned>(&self, key: &str) -> Result<T> {
+        self.get(key)
+            .await?
+            .ok_or_else(|| Error::KeyNotFound(key.into()))
+    }
+
+    /// Retrieves the values associated with each of the provided keys. Keys which do not exist are
+    /// left out of the returned map.
+    pub async fn get_multiple<T: DeserializeOwned>(
+        &self,
+        keys: Vec<impl Deref<Target = str>>,
+    ) -> Result<HashMap<String, T>> {
+        self.get_multiple_with_options(keys, GetOptions::default())
             .await
-            .and_then(|val| {
-                if val.is_undefined() {
-                    Err(JsValue::from("No such value in storage."))
-                } else {
-                    serde_wasm_bindgen::from_value(val).map_err(|e| JsValue::from(e.to_string()))
-                }
-            })
-            .map_err(Error::from)
     }
 
-    /// Retrieves the values associated with each of the provided keys.
-    pub async fn get_multiple(&self, keys: Vec<impl Deref<Target = str>>) -> Result<Map> {
+    /// Retrieves the values associated with each of the provided keys. Keys which do not exist are
+    /// left out of the returned map.
+    pub async fn get_multiple_with_options<T: DeserializeOwned>(
+        &self,
+        keys: Vec<impl Deref<Target = str>>,
+        options: GetOptions,
+    ) -> Result<HashMap<String, T>> {
         let keys = self.inner.get_multiple_internal(
             keys.into_iter()
                 .map(|key| JsValue::from(key.deref()))
                 .collect(),
+            serde_wasm_bindgen::to_value(&options)?,
         )?;
-        let keys = JsFuture::from(keys).await?;
-        keys.dyn_into::<Map>().map_err(Error::from)
+        let map = JsFuture::from(keys).await?;
+        from_stored_map(map)
     }
 
     /// Stores the value and associates it with the given key.
     pub async fn put<T: Serialize>(&mut self, key: &str, value: T) -> Result<()> {
-        JsFuture::from(
-            self.inner
-                .put_internal(key, serde_wasm_bindgen::to_value(&value)?)?,
-        )
+        self.put_with_options(key, value, PutOptions::default())
+            .await
+    }
+
+    /// Stores the value and associates it with the given key.
+    pub async fn put_with_options<T: Serialize>(
+        &mut self,
+        key: &str,
+        value: T,
+        options: PutOptions,
+    ) -> Result<()> {
+        JsFuture::from(self.inner.put_internal(
+            key,
+            serde_wasm_bindgen::to_value(&value)?,
+            serde_wasm_bindgen::to_value(&options)?,
+        )?)
         .await
         .map_err(Error::from)
         .map(|_| ())
@@ -240,19 +525,40 @@ impl Storage {
 
     /// Takes a serializable struct and stores each of its keys and values to storage.
     pub async fn put_multiple<T: Serialize>(&mut self, values: T) -> Result<()> {
+        self.put_multiple_with_options(values, PutOptions::default())
+            .await
+    }
+
+    /// Takes a serializable struct and stores each of its keys and values to storage.
+    pub async fn put_multiple_with_options<T: Serialize>(
+        &mut self,
+        values: T,
+        options: PutOptions,
+    ) -> Result<()> {
         let values = serde_wasm_bindgen::to_value(&values)?;
         if !values.is_object() {
             return Err("Must pass in a struct type".to_string().into());
         }
-        JsFuture::from(self.inner.put_multiple_internal(values)?)
-            .await
-            .map_err(Error::from)
-            .map(|_| ())
+        JsFuture::from(
+            self.inner
+                .put_multiple_internal(values, serde_wasm_bindgen::to_value(&options)?)?,
+        )
+        .await
+        .map_err(Error::from)
+        .map(|_| ())
     }
 
     /// Deletes the key and associated value. Returns true if the key existed or false if it didn't.
     pub async fn delete(&mut self, key: &str) -> Result<bool> {
-        let fut: JsFuture = self.inner.delete_internal(key)?.into();
+        self.delete_with_options(key, PutOptions::default()).await
+    }
+
+    /// Deletes the key and associated value. Returns true if the key existed or false if it didn't.
+    pub async fn delete_with_options(&mut self, key: &str, options: PutOptions) -> Result<bool> {
+        let fut: JsFuture = self
+            .inner
+            .delete_internal(key, serde_wasm_bindgen::to_value(&options)?)?
+            .into();
         fut.await
             .and_then(|jsv| {
                 jsv.as_bool()
@@ -264,12 +570,24 @@ impl Storage {
     /// Deletes the provided keys and their associated values. Returns a count of the number of
     /// key-value pairs deleted.
     pub async fn delete_multiple(&mut self, keys: Vec<impl Deref<Target = str>>) -> Result<usize> {
+        self.delete_multiple_with_options(keys, PutOptions::default())
+            .await
+    }
+
+    /// Deletes the provided keys and their associated values. Returns a count of the number of
+    /// key-value pairs deleted.
+    pub async fn delete_multiple_with_options(
+        &mut self,
+        keys: Vec<impl Deref<Target = str>>,
+        options: PutOptions,
+    ) -> Result<usize> {
         let fut: JsFuture = self
             .inner
             .delete_multiple_internal(
                 keys.into_iter()
                     .map(|key| JsValue::from(key.deref()))
                     .collect(),
+                serde_wasm_bindgen::to_value(&options)?,
             )?
             .into();
         fut.await
@@ -281,6 +599,13 @@ impl Storage {
             .map_err(Error::from)
     }
 
+    /// Resolves once every write made so far, including those made with `allow_unconfirmed`, has
+    /// been persisted to disk.
+    pub async fn sync(&self) -> Result<()> {
+        let fut: JsFuture = self.inner.sync_internal()?.into();
+        fut.await.map(|_| ()).map_err(Error::from)
+    }
+
     /// Deletes all keys and associated values, effectively deallocating all storage used by the
     /// Durable Object. In the event of a failure while the operation is still in flight, it may be
     /// that only a subset of the data is properly deleted.
@@ -315,6 +640,16 @@ impl Storage {
             .map_err(Error::from)
     }
 
+    /// Get a [`TypedStorage`] view over the keys beginning with `prefix`, which encodes keys of
+    /// type `K` and values of type `V` with serde.
+    pub fn typed<K, V>(self, prefix: impl Into<String>) -> TypedStorage<K, V>
+    where
+        K: Serialize + DeserializeOwned,
+        V: Serialize + DeserializeOwned,
+    {
+        TypedStorage::new(self, prefix.into())
+    }
+
     /// Retrieves the current alarm time (if set) as integer milliseconds since epoch.
     /// The alarm is considered to be set if it has not started, or if it has failed
     /// and any retry has not begun. If no alarm is set, `get_alarm()` returns `None`.
@@ -384,59 +719,104 @@ impl Storage {
         fut.await.map(|_| ()).map_err(Error::from)
     }
 
-    // TODO(nilslice): follow up with runtime team on transaction API in general
-    // This function doesn't work on stable yet because the wasm_bindgen `Closure` type is still nightly-gated
-    // #[allow(dead_code)]
-    // async fn transaction<F>(&mut self, closure: fn(Transaction) -> F) -> Result<()>
-    // where
-    //     F: Future<Output = Result<()>> + 'static,
-    // {
-    //     let mut clos = |t: Transaction| {
-    //         future_to_promise(async move {
-    //             closure(t)
-    //                 .await
-    //                 .map_err(JsValue::from)
-    //                 .map(|_| JsValue::NULL)
-    //         })
-    //     };
-    //     JsFuture::from(self.inner.transaction_internal(&mut clos)?)
-    //         .await
-    //         .map_err(Error::from)
-    //         .map(|_| ())
-    // }
-}
-
-#[allow(dead_code)]
-struct Transaction {
+    /// Runs the closure inside a transaction, returning the closure's value once the transaction
+    /// has been committed. If the closure returns an `Err`, the transaction is rolled back and the
+    /// error is returned instead.
+    ///
+    /// ```no_run
+    /// # use worker::*;
+    /// # async fn increment(mut storage: Storage) -> Result<usize> {
+    /// let count = storage
+    ///     .transaction(|mut txn| async move {
+    ///         let count = txn.get_or_default::<usize>("count").await? + 1;
+    ///         txn.put("count", count).await?;
+    ///         Ok(count)
+    ///     })
+    ///     .await?;
+    /// # Ok(count)
+    /// # }
+    /// ```
+    pub async fn transaction<F, Fut, T>(&mut self, closure: F) -> Result<T>
+    where
+        F: FnOnce(Transaction) -> Fut + 'static,
+        Fut: Future<Output = Result<T>> + 'static,
+        T: 'static,
+    {
+        // the closure's result can't always be passed through JS, so it's handed back through here
+        let result = Rc::new(RefCell::new(None));
+        let closure_result = result.clone();
+
+        let closure = Closure::once(move |txn: ObjectTransaction| {
+            future_to_promise(async move {
+                let outcome = closure(Transaction { inner: txn.clone() }).await;
+                let rollback = outcome.is_err();
+                *closure_result.borrow_mut() = Some(outcome);
+
+                if rollback {
+                    txn.rollback_internal()?;
+                }
+                Ok(JsValue::UNDEFINED)
+            })
+        });
+
+        let committed = JsFuture::from(self.inner.transaction_internal(&closure)?).await;
+        let outcome = result.borrow_mut().take();
+        match outcome {
+            Some(Ok(value)) => committed.map(|_| value).map_err(Error::from),
+            Some(Err(e)) => Err(e),
+            None => Err(committed
+                .err()
+                .map(Error::from)
+                .unwrap_or_else(|| "Transaction closure was never called".into())),
+        }
+    }
+}
+
+/// A transaction on a Durable Object's storage, passed to the closure given to
+/// [`Storage::transaction`]. Its methods behave the same as those of [`Storage`], but only take
+/// effect once the whole transaction is committed.
+pub struct Transaction {
     inner: ObjectTransaction,
 }
 
-#[allow(dead_code)]
 impl Transaction {
-    async fn get<T: DeserializeOwned>(&self, key: &str) -> Result<T> {
-        JsFuture::from(self.inner.get_internal(key)?)
-            .await
-            .and_then(|val| {
-                if val.is_undefined() {
-                    Err(JsValue::from("No such value in storage."))
-                } else {
-                    serde_wasm_bindgen::from_value(val).map_err(std::convert::Into::into)
-                }
-            })
-            .map_err(Error::from)
+    /// Retrieves the value associated with the given key, or `None` if the key does not exist.
+    pub async fn get<T: DeserializeOwned>(&self, key: &str) -> Result<Option<T>> {
+        let val = JsFuture::from(self.inner.get_internal(key)?).await?;
+        from_stored_value(val)
+    }
+
+    /// Retrieves the value associated with the given key, or `T::default()` if the key does not
+    /// exist.
+    pub async fn get_or_default<T: DeserializeOwned + Default>(&self, key: &str) -> Result<T> {
+        self.get(key).await.map(Option::unwrap_or_default)
+    }
+
+    /// Retrieves the value associated with the given key, failing with `Error::KeyNotFound` if the
+    /// key does not exist.
+    pub async fn get_required<T: DeserializeOwned>(&self, key: &str) -> Result<T> {
+        self.get(key)
+            .await?
+            .ok_or_else(|| Error::KeyNotFound(key.into()))
     }
 
-    async fn get_multiple(&self, keys: Vec<impl Deref<Target = str>>) -> Result<Map> {
+    /// Retrieves the values associated with each of the provided keys. Keys which do not exist are
+    /// left out of the returned map.
+    pub async fn get_multiple<T: DeserializeOwned>(
+        &self,
+        keys: Vec<impl Deref<Target = str>>,
+    ) -> Result<HashMap<String, T>> {
         let keys = self.inner.get_multiple_internal(
             keys.into_iter()
                 .map(|key| JsValue::from(key.deref()))
                 .collect(),
         )?;
-        let keys = JsFuture::from(keys).await?;
-        keys.dyn_into::<Map>().map_err(Error::from)
+        let map = JsFuture::from(keys).await?;
+        from_stored_map(map)
     }
 
-    async fn put<T: Serialize>(&mut self, key: &str, value: T) -> Result<()> {
+    /// Stores the value and associates it with the given key.
+    pub async fn put<T: Serialize>(&mut self, key: &str, value: T) -> Result<()> {
         JsFuture::from(
             self.inner
                 .put_internal(key, serde_wasm_bindgen::to_value(&value)?)?,
@@ -446,8 +826,8 @@ impl Transaction {
         .map(|_| ())
     }
 
-    // Each key-value pair in the serialized object will be added to the storage
-    async fn put_multiple<T: Serialize>(&mut self, values: T) -> Result<()> {
+    /// Takes a serializable struct and stores each of its keys and values to storage.
+    pub async fn put_multiple<T: Serialize>(&mut self, values: T) -> Result<()> {
         let values = serde_wasm_bindgen::to_value(&values)?;
         if !values.is_object() {
             return Err("Must pass in a struct type".to_string().into());
@@ -458,7 +838,8 @@ impl Transaction {
             .map(|_| ())
     }
 
-    async fn delete(&mut self, key: &str) -> Result<bool> {
+    /// Deletes the key and associated value. Returns true if the key existed or false if it didn't.
+    pub async fn delete(&mut self, key: &str) -> Result<bool> {
         let fut: JsFuture = self.inner.delete_internal(key)?.into();
         fut.await
             .and_then(|jsv| {
@@ -468,7 +849,9 @@ impl Transaction {
             .map_err(Error::from)
     }
 
-    async fn delete_multiple(&mut self, keys: Vec<impl Deref<Target = str>>) -> Result<usize> {
+    /// Deletes the provided keys and their associated values. Returns a count of the number of
+    /// key-value pairs deleted.
+    pub async fn delete_multiple(&mut self, keys: Vec<impl Deref<Target = str>>) -> Result<usize> {
         let fut: JsFuture = self
             .inner
             .delete_multiple_internal(
@@ -486,19 +869,22 @@ impl Transaction {
             .map_err(Error::from)
     }
 
-    async fn delete_all(&mut self) -> Result<()> {
+    /// Deletes all keys and associated values.
+    pub async fn delete_all(&mut self) -> Result<()> {
         let fut: JsFuture = self.inner.delete_all_internal()?.into();
         fut.await.map(|_| ()).map_err(Error::from)
     }
 
-    async fn list(&self) -> Result<Map> {
+    /// Returns all keys and values in ascending lexicographic sorted order.
+    pub async fn list(&self) -> Result<Map> {
         let fut: JsFuture = self.inner.list_internal()?.into();
         fut.await
             .and_then(|jsv| jsv.dyn_into())
             .map_err(Error::from)
     }
 
-    async fn list_with_options(&self, opts: ListOptions<'_>) -> Result<Map> {
+    /// Returns keys according to the parameters in the provided options object.
+    pub async fn list_with_options(&self, opts: ListOptions<'_>) -> Result<Map> {
         let fut: JsFuture = self
             .inner
             .list_with_options_internal(serde_wasm_bindgen::to_value(&opts)?.into())?
@@ -508,11 +894,36 @@ impl Transaction {
             .map_err(Error::from)
     }
 
-    fn rollback(&mut self) -> Result<()> {
+    /// Aborts the transaction, discarding all of its writes. The transaction is rolled back
+    /// automatically when the closure returns an `Err`.
+    pub fn rollback(&mut self) -> Result<()> {
         self.inner.rollback_internal().map_err(Error::from)
     }
 }
 
+fn from_stored_value<T: DeserializeOwned>(val: JsValue) -> Result<Option<T>> {
+    if val.is_undefined() {
+        Ok(None)
+    } else {
+        Ok(Some(serde_wasm_bindgen::from_value(val)?))
+    }
+}
+
+fn from_stored_map<T: DeserializeOwned>(map: JsValue) -> Result<HashMap<String, T>> {
+    map.dyn_into::<Map>()?
+        .entries()
+        .into_iter()
+        .map(|entry| {
+            let entry: Array = entry?.unchecked_into();
+            let key = entry
+                .get(0)
+                .as_string()
+                .ok_or_else(|| Error::RustError("storage key wasn't a string".into()))?;
+            Ok((key, serde_wasm_bindgen::from_value(entry.get(1))?))
+        })
+        .collect()
+}
+
 #[derive(Default, Serialize)]
 pub struct ListOptions<'a> {
     /// Key at which the list results should start, inclusive.
@@ -633,6 +1044,39 @@ impl From<Duration> for ScheduledTime {
     }
 }
 
+/// Options for reading from a Durable Object's [`Storage`], e.g. with
+/// [`Storage::get_with_options`].
+#[derive(Debug, Clone, Default, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GetOptions {
+    /// Allow other events to be delivered while the read is in progress. By default, events are
+    /// held back until the read completes.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub allow_concurrency: Option<bool>,
+    /// Don't keep the value in the in-memory cache once it has been read. It is still read from
+    /// the cache if it is already there.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub no_cache: Option<bool>,
+}
+
+/// Options for writing to a Durable Object's [`Storage`], e.g. with
+/// [`Storage::put_with_options`] or [`Storage::delete_with_options`].
+#[derive(Debug, Clone, Default, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PutOptions {
+    /// Allow other events to be delivered while the write is in progress.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub allow_concurrency: Option<bool>,
+    /// Send outgoing messages without waiting for the write to be confirmed as durable. A write
+    /// which then fails is still reported, by resetting the object. Use [`Storage::sync`] to wait
+    /// for such writes explicitly.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub allow_unconfirmed: Option<bool>,
+    /// Evict the value from the in-memory cache once it has been written.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub no_cache: Option<bool>,
+}
+
 #[derive(Debug, Clone, Default, Serialize)]
 pub struct GetAlarmOptions {
     #[serde(skip_serializing_if = "Option::is_none")]
@@ -647,6 +1091,29 @@ pub struct SetAlarmOptions {
     pub allow_unconfirmed: Option<bool>,
 }
 
+/// Details about an invocation of [`DurableObject::alarm`].
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AlarmInvocationInfo {
+    /// The number of times the alarm has been retried after failing, or 0 for the first attempt.
+    pub retry_count: u32,
+    /// Whether this invocation is a retry of an alarm which previously failed.
+    pub is_retry: bool,
+}
+
+impl TryFrom<JsValue> for AlarmInvocationInfo {
+    type Error = Error;
+
+    fn try_from(info: JsValue) -> Result<Self> {
+        // runtimes which don't pass the invocation info only ever make a first attempt as far as
+        // the object can tell
+        if info.is_undefined() {
+            return Ok(Self::default());
+        }
+        Ok(serde_wasm_bindgen::from_value(info)?)
+    }
+}
+
 impl EnvBinding for ObjectNamespace {
     const TYPE_NAME: &'static str = "DurableObjectNamespace";
 }
@@ -717,7 +1184,47 @@ impl DurableObject for Chatroom {
 pub trait DurableObject {
     fn new(state: State, env: Env) -> Self;
     async fn fetch(&mut self, req: Request) -> Result<Response>;
-    async fn alarm(&mut self) -> Result<Response> {
-        unimplemented!("alarm() handler not implemented")
+
+    /// Called when the alarm set with [`Storage::set_alarm`] fires. Returning an error makes the
+    /// runtime retry the alarm, which `info` tells apart from the first attempt.
+    ///
+    /// `#[durable_object]` exports `alarm` even if the impl doesn't define it, so setting an alarm
+    /// always succeeds. The default implementation then logs and returns an error naming the
+    /// missing handler, which the runtime reports as a failed alarm and retries.
+    #[allow(unused_variables)]
+    async fn alarm(&mut self, info: AlarmInvocationInfo) -> Result<Response> {
+        let message =
+            "alarm fired on a Durable Object which doesn't implement `DurableObject::alarm`";
+        crate::console_error!("{}", message);
+        Err(Error::RustError(message.into()))
+    }
+
+    /// Called for every message received by a WebSocket accepted with
+    /// [`State::accept_web_socket`].
+    #[allow(unused_variables)]
+    async fn websocket_message(
+        &mut self,
+        ws: WebSocket,
+        message: WebSocketIncomingMessage,
+    ) -> Result<()> {
+        Ok(())
+    }
+
+    /// Called when a WebSocket accepted with [`State::accept_web_socket`] is closed by the client.
+    #[allow(unused_variables)]
+    async fn websocket_close(
+        &mut self,
+        ws: WebSocket,
+        code: u16,
+        reason: String,
+        was_clean: bool,
+    ) -> Result<()> {
+        Ok(())
+    }
+
+    /// Called when a WebSocket accepted with [`State::accept_web_socket`] fails.
+    #[allow(unused_variables)]
+    async fn websocket_error(&mut self, ws: WebSocket, error: Error) -> Result<()> {
+        Ok(())
     }
 }
@@ -0,0 +1,504 @@
+use std::{convert::TryFrom, vec::IntoIter};
+
+use serde::{
+    de::{self, value::Error, DeserializeOwned, IntoDeserializer, SeqAccess, Unexpected, Visitor},
+    forward_to_deserialize_any,
+    ser::{self, Impossible, Serialize},
+};
+
+/// Separates the components of a composite key, such as the fields of a tuple or struct. It sorts
+/// before any printable character, so keys list in the order of their components.
+pub(crate) const SEPARATOR: char = '\u{1f}';
+/// The first character after `SEPARATOR`, used as the exclusive end of a listing.
+pub(crate) const SEPARATOR_END: char = '\u{20}';
+/// Escapes occurrences of `SEPARATOR` and itself within string components.
+const ESCAPE: char = '\u{1b}';
+
+// Signed integers are offset by flipping their sign bit, so they sort below positive ones.
+const SIGN_BIT: u64 = 1 << 63;
+
+/// Encode a value as a storage key which sorts the same way the value does: components are
+/// joined by `SEPARATOR` and integers are zero-padded to a fixed width. The one exception are
+/// strings containing characters below `SEPARATOR_END`, which sort below `SEPARATOR` and so before
+/// the strings they extend.
+pub(crate) fn to_key<T: Serialize + ?Sized>(value: &T) -> Result<String, Error> {
+    let mut serializer = KeySerializer {
+        key: String::new(),
+        components: 0,
+    };
+    value.serialize(&mut serializer)?;
+    Ok(serializer.key)
+}
+
+/// Decode a storage key encoded by `to_key`.
+pub(crate) fn from_key<T: DeserializeOwned>(key: &str) -> Result<T, Error> {
+    let mut deserializer = KeyDeserializer {
+        components: split(key).into_iter(),
+    };
+    let value = T::deserialize(&mut deserializer)?;
+
+    match deserializer.components.len() {
+        0 => Ok(value),
+        n => Err(de::Error::custom(format!(
+            "key has {n} components left over"
+        ))),
+    }
+}
+
+fn split(key: &str) -> Vec<String> {
+    let mut components = vec![String::new()];
+    let mut chars = key.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            ESCAPE => components.last_mut().unwrap().extend(chars.next()),
+            SEPARATOR => components.push(String::new()),
+            c => components.last_mut().unwrap().push(c),
+        }
+    }
+    components
+}
+
+struct KeySerializer {
+    key: String,
+    components: usize,
+}
+
+impl KeySerializer {
+    fn push(&mut self, component: &str) {
+        if self.components > 0 {
+            self.key.push(SEPARATOR);
+        }
+        self.components += 1;
+
+        for c in component.chars() {
+            if c == SEPARATOR || c == ESCAPE {
+                self.key.push(ESCAPE);
+            }
+            self.key.push(c);
+        }
+    }
+
+    fn push_unsigned(&mut self, value: u64) {
+        self.push(&format!("{value:020}"));
+    }
+
+    fn push_signed(&mut self, value: i64) {
+        self.push_unsigned(value as u64 ^ SIGN_BIT);
+    }
+}
+
+fn unsupported(what: &str) -> Error {
+    ser::Error::custom(format!("{what} can't be used in a storage key"))
+}
+
+impl ser::Serializer for &mut KeySerializer {
+    type Ok = ();
+    type Error = Error;
+
+    type SerializeSeq = Impossible<(), Error>;
+    type SerializeTuple = Self;
+    type SerializeTupleStruct = Self;
+    type SerializeTupleVariant = Impossible<(), Error>;
+    type SerializeMap = Impossible<(), Error>;
+    type SerializeStruct = Self;
+    type SerializeStructVariant = Impossible<(), Error>;
+
+    fn serialize_bool(self, v: bool) -> Result<(), Error> {
+        self.push(if v { "1" } else { "0" });
+        Ok(())
+    }
+
+    fn serialize_i8(self, v: i8) -> Result<(), Error> {
+        self.serialize_i64(v.into())
+    }
+
+    fn serialize_i16(self, v: i16) -> Result<(), Error> {
+        self.serialize_i64(v.into())
+    }
+
+    fn serialize_i32(self, v: i32) -> Result<(), Error> {
+        self.serialize_i64(v.into())
+    }
+
+    fn serialize_i64(self, v: i64) -> Result<(), Error> {
+        self.push_signed(v);
+        Ok(())
+    }
+
+    fn serialize_u8(self, v: u8) -> Result<(), Error> {
+        self.serialize_u64(v.into())
+    }
+
+    fn serialize_u16(self, v: u16) -> Result<(), Error> {
+        self.serialize_u64(v.into())
+    }
+
+    fn serialize_u32(self, v: u32) -> Result<(), Error> {
+        self.serialize_u64(v.into())
+    }
+
+    fn serialize_u64(self, v: u64) -> Result<(), Error> {
+        self.push_unsigned(v);
+        Ok(())
+    }
+
+    fn serialize_f32(self, _: f32) -> Result<(), Error> {
+        Err(unsupported("a float"))
+    }
+
+    fn serialize_f64(self, _: f64) -> Result<(), Error> {
+        Err(unsupported("a float"))
+    }
+
+    fn serialize_char(self, v: char) -> Result<(), Error> {
+        self.push(v.encode_utf8(&mut [0; 4]));
+        Ok(())
+    }
+
+    fn serialize_str(self, v: &str) -> Result<(), Error> {
+        self.push(v);
+        Ok(())
+    }
+
+    fn serialize_bytes(self, _: &[u8]) -> Result<(), Error> {
+        Err(unsupported("a byte array"))
+    }
+
+    fn serialize_none(self) -> Result<(), Error> {
+        Err(unsupported("an option"))
+    }
+
+    fn serialize_some<T: Serialize + ?Sized>(self, _: &T) -> Result<(), Error> {
+        Err(unsupported("an option"))
+    }
+
+    fn serialize_unit(self) -> Result<(), Error> {
+        self.push("");
+        Ok(())
+    }
+
+    fn serialize_unit_struct(self, _name: &'static str) -> Result<(), Error> {
+        self.serialize_unit()
+    }
+
+    fn serialize_unit_variant(
+        self,
+        _name: &'static str,
+        _index: u32,
+        variant: &'static str,
+    ) -> Result<(), Error> {
+        self.push(variant);
+        Ok(())
+    }
+
+    fn serialize_newtype_struct<T: Serialize + ?Sized>(
+        self,
+        _name: &'static str,
+        value: &T,
+    ) -> Result<(), Error> {
+        value.serialize(self)
+    }
+
+    fn serialize_newtype_variant<T: Serialize + ?Sized>(
+        self,
+        _name: &'static str,
+        _index: u32,
+        _variant: &'static str,
+        _value: &T,
+    ) -> Result<(), Error> {
+        Err(unsupported("an enum variant with fields"))
+    }
+
+    fn serialize_seq(self, _len: Option<usize>) -> Result<Self::SerializeSeq, Error> {
+        Err(unsupported("a sequence"))
+    }
+
+    fn serialize_tuple(self, _len: usize) -> Result<Self, Error> {
+        Ok(self)
+    }
+
+    fn serialize_tuple_struct(self, _name: &'static str, _len: usize) -> Result<Self, Error> {
+        Ok(self)
+    }
+
+    fn serialize_tuple_variant(
+        self,
+        _name: &'static str,
+        _index: u32,
+        _variant: &'static str,
+        _len: usize,
+    ) -> Result<Self::SerializeTupleVariant, Error> {
+        Err(unsupported("an enum variant with fields"))
+    }
+
+    fn serialize_map(self, _len: Option<usize>) -> Result<Self::SerializeMap, Error> {
+        Err(unsupported("a map"))
+    }
+
+    fn serialize_struct(self, _name: &'static str, _len: usize) -> Result<Self, Error> {
+        Ok(self)
+    }
+
+    fn serialize_struct_variant(
+        self,
+        _name: &'static str,
+        _index: u32,
+        _variant: &'static str,
+        _len: usize,
+    ) -> Result<Self::SerializeStructVariant, Error> {
+        Err(unsupported("an enum variant with fields"))
+    }
+}
+
+impl ser::SerializeTuple for &mut KeySerializer {
+    type Ok = ();
+    type Error = Error;
+
+    fn serialize_element<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), Error> {
+        value.serialize(&mut **self)
+    }
+
+    fn end(self) -> Result<(), Error> {
+        Ok(())
+    }
+}
+
+impl ser::SerializeTupleStruct for &mut KeySerializer {
+    type Ok = ();
+    type Error = Error;
+
+    fn serialize_field<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), Error> {
+        value.serialize(&mut **self)
+    }
+
+    fn end(self) -> Result<(), Error> {
+        Ok(())
+    }
+}
+
+impl ser::SerializeStruct for &mut KeySerializer {
+    type Ok = ();
+    type Error = Error;
+
+    fn serialize_field<T: Serialize + ?Sized>(
+        &mut self,
+        _key: &'static str,
+        value: &T,
+    ) -> Result<(), Error> {
+        value.serialize(&mut **self)
+    }
+
+    fn end(self) -> Result<(), Error> {
+        Ok(())
+    }
+}
+
+struct KeyDeserializer {
+    components: IntoIter<String>,
+}
+
+impl KeyDeserializer {
+    fn next(&mut self) -> Result<String, Error> {
+        self.components
+            .next()
+            .ok_or_else(|| de::Error::custom("key has too few components"))
+    }
+
+    fn unsigned(&mut self) -> Result<u64, Error> {
+        let component = self.next()?;
+        component
+            .parse()
+            .map_err(|_| de::Error::invalid_value(Unexpected::Str(&component), &"an integer"))
+    }
+
+    fn signed(&mut self) -> Result<i64, Error> {
+        Ok((self.unsigned()? ^ SIGN_BIT) as i64)
+    }
+}
+
+macro_rules! deserialize_integer {
+    ($($method:ident => $visit:ident($ty:ty) from $read:ident as $unexpected:ident,)*) => {$(
+        fn $method<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+            let value = self.$read()?;
+            match <$ty>::try_from(value) {
+                Ok(value) => visitor.$visit(value),
+                Err(_) => Err(de::Error::invalid_value(Unexpected::$unexpected(value), &visitor)),
+            }
+        }
+    )*};
+}
+
+impl<'de> de::Deserializer<'de> for &mut KeyDeserializer {
+    type Error = Error;
+
+    fn deserialize_any<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+        visitor.visit_string(self.next()?)
+    }
+
+    fn deserialize_bool<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+        match self.next()?.as_str() {
+            "0" => visitor.visit_bool(false),
+            "1" => visitor.visit_bool(true),
+            other => Err(de::Error::invalid_value(Unexpected::Str(other), &visitor)),
+        }
+    }
+
+    deserialize_integer! {
+        deserialize_i8 => visit_i8(i8) from signed as Signed,
+        deserialize_i16 => visit_i16(i16) from signed as Signed,
+        deserialize_i32 => visit_i32(i32) from signed as Signed,
+        deserialize_i64 => visit_i64(i64) from signed as Signed,
+        deserialize_u8 => visit_u8(u8) from unsigned as Unsigned,
+        deserialize_u16 => visit_u16(u16) from unsigned as Unsigned,
+        deserialize_u32 => visit_u32(u32) from unsigned as Unsigned,
+        deserialize_u64 => visit_u64(u64) from unsigned as Unsigned,
+    }
+
+    fn deserialize_unit<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+        self.next()?;
+        visitor.visit_unit()
+    }
+
+    fn deserialize_unit_struct<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        visitor: V,
+    ) -> Result<V::Value, Error> {
+        self.deserialize_unit(visitor)
+    }
+
+    fn deserialize_newtype_struct<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        visitor: V,
+    ) -> Result<V::Value, Error> {
+        visitor.visit_newtype_struct(self)
+    }
+
+    fn deserialize_tuple<V: Visitor<'de>>(self, len: usize, visitor: V) -> Result<V::Value, Error> {
+        visitor.visit_seq(ComponentsAccess {
+            deserializer: self,
+            remaining: len,
+        })
+    }
+
+    fn deserialize_tuple_struct<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        len: usize,
+        visitor: V,
+    ) -> Result<V::Value, Error> {
+        self.deserialize_tuple(len, visitor)
+    }
+
+    fn deserialize_struct<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        fields: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, Error> {
+        self.deserialize_tuple(fields.len(), visitor)
+    }
+
+    fn deserialize_enum<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        _variants: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, Error> {
+        visitor.visit_enum(self.next()?.into_deserializer())
+    }
+
+    fn deserialize_ignored_any<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+        self.deserialize_unit(visitor)
+    }
+
+    forward_to_deserialize_any! {
+        f32 f64 char str string bytes byte_buf option seq map identifier
+    }
+}
+
+struct ComponentsAccess<'a> {
+    deserializer: &'a mut KeyDeserializer,
+    remaining: usize,
+}
+
+impl<'de> SeqAccess<'de> for ComponentsAccess<'_> {
+    type Error = Error;
+
+    fn next_element_seed<T: de::DeserializeSeed<'de>>(
+        &mut self,
+        seed: T,
+    ) -> Result<Option<T::Value>, Error> {
+        if self.remaining == 0 {
+            return Ok(None);
+        }
+        self.remaining -= 1;
+        seed.deserialize(&mut *self.deserializer).map(Some)
+    }
+
+    fn size_hint(&self) -> Option<usize> {
+        Some(self.remaining)
+    }
+}
+
+#[test]
+fn keys_sort_like_values() {
+    use serde::{Deserialize, Serialize};
+
+    #[derive(Debug, PartialEq, PartialOrd, Serialize, Deserialize)]
+    struct Entry {
+        account: String,
+        day: i32,
+        seq: u16,
+    }
+
+    let mut entries = vec![
+        Entry {
+            account: "ab".into(),
+            day: -1,
+            seq: 0,
+        },
+        Entry {
+            account: "a".into(),
+            day: 10,
+            seq: 2,
+        },
+        Entry {
+            account: "a".into(),
+            day: 9,
+            seq: 7,
+        },
+        Entry {
+            account: "a b".into(),
+            day: -300,
+            seq: 1,
+        },
+    ];
+
+    let mut keys = entries
+        .iter()
+        .map(|entry| to_key(entry).unwrap())
+        .collect::<Vec<_>>();
+    keys.sort();
+    entries.sort_by(|a, b| a.partial_cmp(b).unwrap());
+
+    let decoded = keys
+        .iter()
+        .map(|key| from_key::<Entry>(key).unwrap())
+        .collect::<Vec<_>>();
+    assert_eq!(decoded, entries);
+
+    assert_eq!(
+        from_key::<(String, u8)>(&to_key(&("x", 255u64)).unwrap()).unwrap(),
+        ("x".to_string(), 255)
+    );
+    assert_eq!(
+        from_key::<String>(&to_key("a\u{1f}b\u{1b}").unwrap()).unwrap(),
+        "a\u{1f}b\u{1b}"
+    );
+    assert!(from_key::<u8>(&to_key(&256u64).unwrap()).is_err());
+    assert!(from_key::<String>(&to_key(&("x", 1)).unwrap()).is_err());
+    assert!(to_key(&vec![1, 2]).is_err());
+    assert!(to_key(&1.5).is_err());
+}
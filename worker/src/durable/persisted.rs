@@ -0,0 +1,333 @@
+use std::ops::{Deref, DerefMut};
+
+use futures_util::future::LocalBoxFuture;
+use js_sys::Object;
+use serde::{de::DeserializeOwned, Serialize};
+use wasm_bindgen::JsValue;
+use wasm_bindgen_futures::JsFuture;
+
+use super::{State, Storage};
+use crate::{Error, Result};
+
+/// The most chunks a value can be split into, so that all of them and the chunk count are written
+/// by a single, atomic `put`.
+const MAX_CHUNKS: usize = 127;
+
+/// A value kept in a Durable Object's memory and persisted under a key of its [`Storage`],
+/// created with [`State::persisted`].
+///
+/// The value is loaded from storage the first time it is accessed, and `T::default()` is used if
+/// nothing has been stored yet. Changes made through `get_mut` or `set` only mark it as dirty;
+/// call `flush` once a request is done with it to write it back and handle any error. Changes
+/// which haven't been flushed are written back in the background with [`State::wait_until`]
+/// when the `Persisted` is dropped, or when the guard returned by `modify` is, e.g. at the end of
+/// the request which made them. Such a write is started at once, so later writes to the same key
+/// still take precedence.
+///
+/// Values larger than storage allows under a single key can be split into chunks of JSON with
+/// `with_chunk_size`. The chunk count is stored under the key itself and the chunks under
+/// `<key>:0`, `<key>:1` and so on, so the same key must always be read with the same setting.
+///
+/// ```no_run
+/// # use worker::*;
+/// # #[derive(Default, serde::Serialize, serde::Deserialize)]
+/// # struct Stats { visits: u64 }
+/// # async fn example(state: State) -> Result<()> {
+/// let mut stats = state.persisted::<Stats>("stats");
+/// stats.get_mut().await?.visits += 1;
+/// stats.flush().await?;
+///
+/// // written back in the background once `guard` goes out of scope
+/// let mut guard = stats.modify().await?;
+/// guard.visits += 1;
+/// # Ok(())
+/// # }
+/// ```
+pub struct Persisted<T: Serialize> {
+    state: State,
+    storage: Storage,
+    key: String,
+    value: Option<T>,
+    dirty: bool,
+    chunk_size: Option<usize>,
+    /// The number of chunks currently in storage, so that ones left over by a larger value can be
+    /// deleted, or `None` if it hasn't been read yet.
+    chunks: Option<usize>,
+}
+
+impl<T> Persisted<T>
+where
+    T: Serialize + DeserializeOwned + Default,
+{
+    pub(super) fn new(state: &State, key: String) -> Self {
+        Self {
+            state: State {
+                inner: state.inner.clone(),
+            },
+            storage: state.storage(),
+            key,
+            value: None,
+            dirty: false,
+            chunk_size: None,
+            chunks: None,
+        }
+    }
+
+    /// Store the value as JSON split into chunks of at most `chunk_size` bytes, so that it can
+    /// exceed the size limit of a single value. It can be split into at most 127 chunks.
+    pub fn with_chunk_size(mut self, chunk_size: usize) -> Self {
+        self.chunk_size = Some(chunk_size);
+        self
+    }
+
+    /// The value, loading it from storage if it hasn't been yet.
+    pub async fn get(&mut self) -> Result<&T> {
+        self.load().await?;
+        Ok(self.value.get_or_insert_with(T::default))
+    }
+
+    /// The value for modification, loading it from storage if it hasn't been yet. It will be
+    /// written back by the next `flush`.
+    pub async fn get_mut(&mut self) -> Result<&mut T> {
+        self.load().await?;
+        self.dirty = true;
+        Ok(self.value.get_or_insert_with(T::default))
+    }
+
+    /// The value for modification, loading it from storage if it hasn't been yet. It is written
+    /// back in the background when the returned guard is dropped.
+    pub async fn modify(&mut self) -> Result<PersistedMut<'_, T>> {
+        self.get_mut().await?;
+        Ok(PersistedMut { persisted: self })
+    }
+
+    /// Replaces the value without loading the stored one. It will be written back by the next
+    /// `flush`.
+    pub fn set(&mut self, value: T) {
+        self.value = Some(value);
+        self.dirty = true;
+    }
+
+    /// Whether the value has changes which haven't been flushed to storage.
+    pub fn is_dirty(&self) -> bool {
+        self.dirty
+    }
+
+    /// Writes the value back to storage if it has changed since it was loaded or last flushed.
+    pub async fn flush(&mut self) -> Result<()> {
+        let write = match self.take_write()? {
+            Some(write) => write,
+            None => return Ok(()),
+        };
+
+        if let Err(e) = write.await {
+            // the write may have been partial, so the value and its chunks have to be written again
+            self.dirty = true;
+            self.chunks = None;
+            return Err(e);
+        }
+        Ok(())
+    }
+
+    async fn load(&mut self) -> Result<()> {
+        if self.value.is_none() {
+            self.value = Some(self.read().await?.unwrap_or_default());
+        }
+        Ok(())
+    }
+
+    async fn read(&mut self) -> Result<Option<T>> {
+        if self.chunk_size.is_none() {
+            return self.storage.get(&self.key).await;
+        }
+
+        let count: usize = match self.storage.get(&self.key).await? {
+            Some(count) => count,
+            None => {
+                self.chunks = Some(0);
+                return Ok(None);
+            }
+        };
+        let keys: Vec<String> = (0..count).map(|i| chunk_key(&self.key, i)).collect();
+        let mut chunks = self.storage.get_multiple::<String>(keys.clone()).await?;
+
+        let mut json = String::new();
+        for key in keys {
+            let chunk = chunks
+                .remove(&key)
+                .ok_or_else(|| Error::RustError(format!("chunk `{}` is missing", key)))?;
+            json.push_str(&chunk);
+        }
+        self.chunks = Some(count);
+
+        Ok(Some(serde_json::from_str(&json)?))
+    }
+}
+
+impl<T: Serialize> Persisted<T> {
+    /// Serializes the value if it is dirty and marks it as clean, then starts writing it to
+    /// storage and returns the pending write. The write is started right away, so that it's
+    /// ordered before any later write to the same key even if it's awaited in the background.
+    fn take_write(&mut self) -> Result<Option<LocalBoxFuture<'static, Result<()>>>> {
+        let value = match (&self.value, self.dirty) {
+            (Some(value), true) => value,
+            _ => return Ok(None),
+        };
+
+        let entries = Object::new();
+        let mut stale = vec![];
+        match self.chunk_size {
+            None => {
+                js_sys::Reflect::set(
+                    &entries,
+                    &self.key.as_str().into(),
+                    &serde_wasm_bindgen::to_value(value)?,
+                )?;
+            }
+            Some(chunk_size) => {
+                let json = serde_json::to_string(value)?;
+                let chunks = split_chunks(&json, chunk_size);
+                if chunks.len() > MAX_CHUNKS {
+                    return Err(Error::RustError(format!(
+                        "`{}` is too large to be stored in {} chunks of {} bytes",
+                        self.key, MAX_CHUNKS, chunk_size
+                    )));
+                }
+
+                js_sys::Reflect::set(&entries, &self.key.as_str().into(), &chunks.len().into())?;
+                for (i, chunk) in chunks.iter().enumerate() {
+                    js_sys::Reflect::set(
+                        &entries,
+                        &chunk_key(&self.key, i).into(),
+                        &(*chunk).into(),
+                    )?;
+                }
+
+                // chunks left over by a larger value are deleted, all of the ones it could have
+                // had if the stored value was never read
+                let stored = self.chunks.unwrap_or(MAX_CHUNKS);
+                stale = (chunks.len()..stored)
+                    .map(|i| chunk_key(&self.key, i).into())
+                    .collect();
+                self.chunks = Some(chunks.len());
+            }
+        }
+        self.dirty = false;
+
+        let put = JsFuture::from(
+            self.storage
+                .inner
+                .put_multiple_internal(entries.into(), JsValue::UNDEFINED)?,
+        );
+        let delete = if stale.is_empty() {
+            None
+        } else {
+            Some(JsFuture::from(
+                self.storage
+                    .inner
+                    .delete_multiple_internal(stale, JsValue::UNDEFINED)?,
+            ))
+        };
+        Ok(Some(Box::pin(async move {
+            put.await?;
+            if let Some(delete) = delete {
+                delete.await?;
+            }
+            Ok(())
+        })))
+    }
+
+    /// Writes the value back with [`State::wait_until`] if it is dirty, logging any error since
+    /// there's no one left to handle it.
+    fn flush_in_background(&mut self) {
+        match self.take_write() {
+            Ok(Some(write)) => {
+                let key = self.key.clone();
+                self.state.wait_until(async move {
+                    if let Err(e) = write.await {
+                        crate::console_error!("failed to write `{}` back to storage: {}", key, e);
+                    }
+                });
+            }
+            Ok(None) => {}
+            Err(e) => {
+                crate::console_error!("failed to write `{}` back to storage: {}", self.key, e)
+            }
+        }
+    }
+}
+
+impl<T: Serialize> Drop for Persisted<T> {
+    fn drop(&mut self) {
+        self.flush_in_background();
+    }
+}
+
+/// Mutable access to the value of a [`Persisted`], returned by `Persisted::modify`. The value is
+/// written back in the background with [`State::wait_until`] when the guard is dropped.
+pub struct PersistedMut<'a, T: Serialize> {
+    persisted: &'a mut Persisted<T>,
+}
+
+impl<T: Serialize> Deref for PersistedMut<'_, T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        self.persisted
+            .value
+            .as_ref()
+            .expect("the value is loaded by `modify`")
+    }
+}
+
+impl<T: Serialize> DerefMut for PersistedMut<'_, T> {
+    fn deref_mut(&mut self) -> &mut T {
+        self.persisted.dirty = true;
+        self.persisted
+            .value
+            .as_mut()
+            .expect("the value is loaded by `modify`")
+    }
+}
+
+impl<T: Serialize> Drop for PersistedMut<'_, T> {
+    fn drop(&mut self) {
+        self.persisted.flush_in_background();
+    }
+}
+
+fn chunk_key(key: &str, i: usize) -> String {
+    format!("{}:{}", key, i)
+}
+
+/// Splits `s` into chunks of at most `size` bytes without splitting any characters, unless a
+/// character is wider than `size`, in which case it gets a chunk of its own.
+fn split_chunks(s: &str, size: usize) -> Vec<&str> {
+    let mut chunks = vec![];
+    let mut rest = s;
+
+    while !rest.is_empty() {
+        let mut end = size.min(rest.len());
+        while !rest.is_char_boundary(end) {
+            end -= 1;
+        }
+        if end == 0 {
+            end = rest.chars().next().map_or(0, char::len_utf8);
+        }
+
+        let (chunk, tail) = rest.split_at(end);
+        chunks.push(chunk);
+        rest = tail;
+    }
+
+    chunks
+}
+
+#[test]
+fn chunks_split_on_char_boundaries() {
+    assert_eq!(split_chunks("", 3), Vec::<&str>::new());
+    assert_eq!(split_chunks("abcdefg", 3), vec!["abc", "def", "g"]);
+    assert_eq!(split_chunks("aé€b", 3), vec!["aé", "€", "b"]);
+    assert_eq!(split_chunks("€€", 2), vec!["€", "€"]);
+    assert_eq!(split_chunks("abc", 0), vec!["a", "b", "c"]);
+}
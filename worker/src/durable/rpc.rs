@@ -0,0 +1,132 @@
+use std::fmt::Display;
+
+use serde::{de::DeserializeOwned, Serialize};
+use wasm_bindgen::JsValue;
+
+use super::Stub;
+use crate::{Error, Headers, Method, Request, RequestInit, Response, Result};
+
+/// The path under which calls to RPC methods are sent to a Durable Object.
+const RPC_PATH: &str = "/__rpc/";
+
+/// An error returned by a method of a stub generated with `#[durable_object_rpc]`.
+#[derive(Debug, thiserror::Error)]
+pub enum RpcError<E> {
+    /// The Durable Object's method ran and returned this error.
+    #[error("{0}")]
+    Method(E),
+    /// The method couldn't be called, or its result couldn't be read.
+    #[error(transparent)]
+    Worker(#[from] Error),
+}
+
+impl<E: Display> From<RpcError<E>> for Error {
+    fn from(e: RpcError<E>) -> Self {
+        match e {
+            RpcError::Method(e) => Error::RustError(e.to_string()),
+            RpcError::Worker(e) => e,
+        }
+    }
+}
+
+/// Whether the request is a call to a method exposed with `#[durable_object_rpc]`, which should
+/// be passed on to the `handle_rpc` method generated for the Durable Object.
+pub fn is_rpc_request(req: &Request) -> bool {
+    req.path().starts_with(RPC_PATH)
+}
+
+/// A call to an RPC method, used by the code `#[durable_object_rpc]` generates.
+///
+/// Calls are `POST` requests to `/__rpc/<method>/v<version>` whose body is the JSON array of
+/// arguments. The method's `Result` is returned as JSON, e.g. `{"Ok":1}`, with a 200 status;
+/// any other status means the method wasn't called.
+#[doc(hidden)]
+pub struct RpcCall {
+    method: String,
+    version: u32,
+    req: Request,
+}
+
+impl RpcCall {
+    /// Calls the method of the Durable Object `stub` points to.
+    pub async fn send<A, T, E>(
+        stub: &Stub,
+        method: &str,
+        version: u32,
+        args: &A,
+    ) -> std::result::Result<T, RpcError<E>>
+    where
+        A: Serialize,
+        T: DeserializeOwned,
+        E: DeserializeOwned,
+    {
+        let body = serde_json::to_string(args).map_err(Error::from)?;
+        let mut headers = Headers::new();
+        headers.set("content-type", "application/json")?;
+        let mut init = RequestInit::new();
+        init.with_method(Method::Post)
+            .with_headers(headers)
+            .with_body(Some(JsValue::from_str(&body)));
+
+        // the host is ignored, but stubs require a full URL
+        let url = format!("https://durable-object{}{}/v{}", RPC_PATH, method, version);
+        let mut resp = stub.fetch_with_init(&url, &init).await?;
+        if resp.status_code() != 200 {
+            return Err(Error::Json(resp.text().await?, resp.status_code()).into());
+        }
+
+        let text = resp.text().await?;
+        serde_json::from_str::<std::result::Result<T, E>>(&text)
+            .map_err(Error::from)?
+            .map_err(RpcError::Method)
+    }
+
+    /// Reads the method and version being called from the request, or returns `None` if it isn't
+    /// an RPC call.
+    pub fn from_request(req: Request) -> Option<Self> {
+        let path = req.path();
+        let (method, version) = path.strip_prefix(RPC_PATH)?.split_once("/v")?;
+
+        Some(Self {
+            method: method.to_string(),
+            version: version.parse().ok()?,
+            req,
+        })
+    }
+
+    pub fn method(&self) -> &str {
+        &self.method
+    }
+
+    pub fn version(&self) -> u32 {
+        self.version
+    }
+
+    /// Reads the method's arguments.
+    pub async fn args<A: DeserializeOwned>(&mut self) -> Result<A> {
+        Ok(serde_json::from_str(&self.req.text().await?)?)
+    }
+
+    /// Responds with the method's result.
+    pub fn respond<T: Serialize, E: Serialize>(
+        result: std::result::Result<T, E>,
+    ) -> Result<Response> {
+        Response::from_json(&result)
+    }
+
+    /// Responds to a call which couldn't be made.
+    pub fn fail(error: Error) -> Result<Response> {
+        Response::error(error.to_string(), error.status_code())
+    }
+
+    /// Responds to a call to a method or version the Durable Object doesn't have.
+    pub fn not_found(&self) -> Result<Response> {
+        Response::error(
+            format!(
+                "no rpc method `{}` with version {}",
+                self.method, self.version
+            ),
+            404,
+        )
+    }
+}
@@ -0,0 +1,192 @@
+use std::{
+    marker::PhantomData,
+    ops::{Bound, RangeBounds},
+};
+
+use futures_util::{stream, Stream, TryStreamExt};
+use js_sys::{Array, Map};
+use serde::{de::DeserializeOwned, Serialize};
+use wasm_bindgen::JsCast;
+
+use super::{
+    key::{self, SEPARATOR, SEPARATOR_END},
+    ListOptions, Storage,
+};
+use crate::{Error, Result};
+
+/// A typed view over the part of a Durable Object's [`Storage`] whose keys begin with a prefix,
+/// created with [`Storage::typed`].
+///
+/// Keys of type `K` are encoded with serde, so they can be strings, integers, unit enum variants,
+/// or tuples and structs of those. Composite keys list in the order of their fields, which makes
+/// it possible to list every entry sharing the leading fields of a key with `list_prefix`, or a
+/// range of keys with `list_range` and `stream_range`. Values of type `V` are stored the same way
+/// `Storage::put` stores them.
+///
+/// Strings containing control characters (below `U+0020`) are stored and read back intact, but
+/// aren't guaranteed to list in order relative to the strings they extend, e.g. `("a\u{1}", 1)`
+/// lists before `("a", 2)`.
+///
+/// ```no_run
+/// # use worker::*;
+/// # async fn example(state: State) -> Result<()> {
+/// // keys are (account, day) pairs
+/// let mut ledger = state.storage().typed::<(String, u32), i64>("ledger");
+/// ledger.put(&("alice".into(), 7), &-20).await?;
+/// ledger.put(&("alice".into(), 12), &45).await?;
+///
+/// let alice = ledger.list_prefix(&("alice",)).await?;
+/// let week = ledger
+///     .list_range(("alice".into(), 7)..("alice".into(), 14))
+///     .await?;
+/// # Ok(())
+/// # }
+/// ```
+pub struct TypedStorage<K, V> {
+    storage: Storage,
+    prefix: String,
+    types: PhantomData<fn() -> (K, V)>,
+}
+
+impl<K, V> TypedStorage<K, V>
+where
+    K: Serialize + DeserializeOwned,
+    V: Serialize + DeserializeOwned,
+{
+    pub(super) fn new(storage: Storage, prefix: String) -> Self {
+        Self {
+            storage,
+            prefix,
+            types: PhantomData,
+        }
+    }
+
+    /// Retrieves the value associated with the given key, if there is one.
+    pub async fn get(&self, key: &K) -> Result<Option<V>> {
+        self.storage.get(&self.key(key)?).await
+    }
+
+    /// Stores the value and associates it with the given key.
+    pub async fn put(&mut self, key: &K, value: &V) -> Result<()> {
+        let key = self.key(key)?;
+        self.storage.put(&key, value).await
+    }
+
+    /// Deletes the key and associated value. Returns true if the key existed or false if it didn't.
+    pub async fn delete(&mut self, key: &K) -> Result<bool> {
+        let key = self.key(key)?;
+        self.storage.delete(&key).await
+    }
+
+    /// Returns every entry whose key begins with the given fields, e.g. `&("alice",)` for keys of
+    /// type `(String, u32)`, in ascending order.
+    pub async fn list_prefix<P: Serialize + ?Sized>(&self, prefix: &P) -> Result<Vec<(K, V)>> {
+        let prefix = format!("{}{}", self.key(prefix)?, SEPARATOR);
+        let map = self
+            .storage
+            .list_with_options(ListOptions::new().prefix(&prefix))
+            .await?;
+        self.entries(&map)
+    }
+
+    /// Returns every entry whose key is within the range in ascending order, e.g. `..` for all of
+    /// them.
+    ///
+    /// All the entries are loaded into memory at once; use `stream_range` to page through large
+    /// ranges instead.
+    pub async fn list_range(&self, range: impl RangeBounds<K>) -> Result<Vec<(K, V)>> {
+        let (start, end) = self.bounds(range)?;
+        let map = self
+            .storage
+            .list_with_options(ListOptions::new().start(&start).end(&end))
+            .await?;
+        self.entries(&map)
+    }
+
+    /// Streams the entries whose key is within the range in ascending order, loading `page_size`
+    /// entries from storage at a time.
+    pub fn stream_range(
+        &self,
+        range: impl RangeBounds<K>,
+        page_size: usize,
+    ) -> Result<impl Stream<Item = Result<(K, V)>> + '_> {
+        let (start, end) = self.bounds(range)?;
+        let page_size = page_size.max(1);
+
+        let pages = stream::try_unfold(Some(start), move |start| {
+            let end = end.clone();
+            async move {
+                let start = match start {
+                    Some(start) => start,
+                    None => return Ok::<_, Error>(None),
+                };
+
+                let map = self
+                    .storage
+                    .list_with_options(ListOptions::new().start(&start).end(&end).limit(page_size))
+                    .await?;
+                let entries = self.entries(&map)?;
+
+                // the next page starts right after the last key of this one
+                let next = match map.keys().into_iter().last() {
+                    Some(last) if map.size() as usize == page_size => {
+                        last?.as_string().map(|last| last + "\0")
+                    }
+                    _ => None,
+                };
+                Ok(Some((entries, next)))
+            }
+        });
+
+        Ok(pages
+            .map_ok(|entries| stream::iter(entries.into_iter().map(Ok)))
+            .try_flatten())
+    }
+
+    fn key<T: Serialize + ?Sized>(&self, key: &T) -> Result<String> {
+        let key =
+            key::to_key(key).map_err(|e| Error::RustError(format!("invalid storage key: {e}")))?;
+        Ok(format!("{}{}{}", self.prefix, SEPARATOR, key))
+    }
+
+    // Listings are bounded by the prefix, as every key starts with the prefix followed by
+    // `SEPARATOR`. Appending a NUL to a key gives the first key after it.
+    fn bounds(&self, range: impl RangeBounds<K>) -> Result<(String, String)> {
+        let start = match range.start_bound() {
+            Bound::Included(key) => self.key(key)?,
+            Bound::Excluded(key) => self.key(key)? + "\0",
+            Bound::Unbounded => format!("{}{}", self.prefix, SEPARATOR),
+        };
+        let end = match range.end_bound() {
+            Bound::Included(key) => self.key(key)? + "\0",
+            Bound::Excluded(key) => self.key(key)?,
+            Bound::Unbounded => format!("{}{}", self.prefix, SEPARATOR_END),
+        };
+
+        Ok((start, end))
+    }
+
+    fn entries(&self, map: &Map) -> Result<Vec<(K, V)>> {
+        let prefix = format!("{}{}", self.prefix, SEPARATOR);
+
+        map.entries()
+            .into_iter()
+            .map(|entry| {
+                let entry: Array = entry?.unchecked_into();
+                let raw_key = entry
+                    .get(0)
+                    .as_string()
+                    .ok_or_else(|| Error::RustError("storage key wasn't a string".into()))?;
+                let key = raw_key
+                    .strip_prefix(&prefix)
+                    .ok_or_else(|| Error::RustError(format!("unexpected storage key: {raw_key}")))
+                    .and_then(|key| {
+                        key::from_key(key)
+                            .map_err(|e| Error::RustError(format!("invalid storage key: {e}")))
+                    })?;
+
+                Ok((key, serde_wasm_bindgen::from_value(entry.get(1))?))
+            })
+            .collect()
+    }
+}
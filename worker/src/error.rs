@@ -41,8 +41,44 @@ pub enum Error {
     #[error("Kv Error: {0}")]
     KvError(String),
 
+    #[error("no value stored for key `{0}`")]
+    KeyNotFound(String),
+
     #[error("url parse error: {0}")]
     UrlParseError(#[from] url::ParseError),
+
+    #[error("http error: {0}")]
+    Http(#[from] http::Error),
+
+    #[error("{0}")]
+    Custom(Box<dyn std::error::Error + Send + Sync>),
+}
+
+impl Error {
+    /// Wrap an error type of your own, so it can be propagated as an `Error` and recovered later,
+    /// e.g. by an error handler, using `downcast_ref`.
+    pub fn custom(error: impl std::error::Error + Send + Sync + 'static) -> Self {
+        Error::Custom(Box::new(error))
+    }
+
+    /// Get a reference to the wrapped error if this is a `Custom` error of type `E`.
+    pub fn downcast_ref<E: std::error::Error + 'static>(&self) -> Option<&E> {
+        match self {
+            Error::Custom(e) => e.downcast_ref(),
+            _ => None,
+        }
+    }
+
+    /// The HTTP status code which best describes this error when it is returned to a client, e.g.
+    /// `415` for a `BadEncoding` error or `400` for a `SerdeJsonError`. Always within `400..=599`.
+    pub fn status_code(&self) -> u16 {
+        match self {
+            Error::BadEncoding => 415,
+            Error::SerdeJsonError(_) => 400,
+            Error::Json(_, status) if (400..=599).contains(status) => *status,
+            _ => 500,
+        }
+    }
 }
 
 impl From<Error> for JsValue {
@@ -86,3 +122,15 @@ impl From<JsValue> for Error {
         Error::JsError(to_string(&value))
     }
 }
+
+#[test]
+fn error_status_codes() {
+    assert_eq!(Error::BadEncoding.status_code(), 415);
+    assert_eq!(
+        Error::from(serde_json::from_str::<u8>("nope").unwrap_err()).status_code(),
+        400
+    );
+    assert_eq!(Error::Json("Gone".into(), 410).status_code(), 410);
+    assert_eq!(Error::Json("OK".into(), 200).status_code(), 500);
+    assert_eq!(Error::BodyUsed.status_code(), 500);
+}
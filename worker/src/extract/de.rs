@@ -0,0 +1,308 @@
+use std::slice::Iter;
+
+use serde::{
+    de::{
+        self, value::Error, DeserializeSeed, Deserializer, IntoDeserializer, MapAccess, SeqAccess,
+        Unexpected, Visitor,
+    },
+    forward_to_deserialize_any,
+};
+
+/// Deserializes a list of string key/value pairs, such as URL parameters or a query string, into
+/// a struct or map (by key), a tuple or sequence (by position), or a single value when the list
+/// contains exactly one pair.
+pub(crate) struct PairsDeserializer<'de> {
+    pairs: &'de [(String, String)],
+    positional: &'de [(String, String)],
+}
+
+impl<'de> PairsDeserializer<'de> {
+    pub(crate) fn new(pairs: &'de [(String, String)]) -> Self {
+        Self {
+            pairs,
+            positional: pairs,
+        }
+    }
+
+    /// Only deserialize the given pairs into a tuple, sequence or single value, while every pair
+    /// can still be deserialized by key.
+    pub(crate) fn with_positional(self, positional: &'de [(String, String)]) -> Self {
+        Self { positional, ..self }
+    }
+
+    fn single(&self) -> Result<ValueDeserializer<'de>, Error> {
+        match self.positional {
+            [(_, value)] => Ok(ValueDeserializer(value)),
+            _ => Err(de::Error::invalid_length(
+                self.positional.len(),
+                &"a single value",
+            )),
+        }
+    }
+}
+
+macro_rules! deserialize_single {
+    ($($method:ident)*) => {$(
+        fn $method<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+            self.single()?.$method(visitor)
+        }
+    )*};
+}
+
+impl<'de> Deserializer<'de> for PairsDeserializer<'de> {
+    type Error = Error;
+
+    fn deserialize_any<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+        self.deserialize_map(visitor)
+    }
+
+    fn deserialize_map<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+        visitor.visit_map(PairsAccess {
+            pairs: self.pairs.iter(),
+            value: None,
+        })
+    }
+
+    fn deserialize_struct<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        _fields: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, Error> {
+        self.deserialize_map(visitor)
+    }
+
+    fn deserialize_seq<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+        visitor.visit_seq(ValuesAccess(self.positional.iter()))
+    }
+
+    fn deserialize_tuple<V: Visitor<'de>>(self, len: usize, visitor: V) -> Result<V::Value, Error> {
+        if self.positional.len() != len {
+            return Err(de::Error::invalid_length(
+                self.positional.len(),
+                &format!("a tuple of {len} values").as_str(),
+            ));
+        }
+        self.deserialize_seq(visitor)
+    }
+
+    fn deserialize_tuple_struct<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        len: usize,
+        visitor: V,
+    ) -> Result<V::Value, Error> {
+        self.deserialize_tuple(len, visitor)
+    }
+
+    fn deserialize_newtype_struct<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        visitor: V,
+    ) -> Result<V::Value, Error> {
+        visitor.visit_newtype_struct(self)
+    }
+
+    fn deserialize_option<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+        if self.pairs.is_empty() {
+            visitor.visit_none()
+        } else {
+            visitor.visit_some(self)
+        }
+    }
+
+    fn deserialize_unit<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+        visitor.visit_unit()
+    }
+
+    fn deserialize_unit_struct<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        visitor: V,
+    ) -> Result<V::Value, Error> {
+        visitor.visit_unit()
+    }
+
+    fn deserialize_ignored_any<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+        visitor.visit_unit()
+    }
+
+    fn deserialize_enum<V: Visitor<'de>>(
+        self,
+        name: &'static str,
+        variants: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, Error> {
+        self.single()?.deserialize_enum(name, variants, visitor)
+    }
+
+    deserialize_single! {
+        deserialize_bool deserialize_i8 deserialize_i16 deserialize_i32 deserialize_i64
+        deserialize_u8 deserialize_u16 deserialize_u32 deserialize_u64 deserialize_f32
+        deserialize_f64 deserialize_char deserialize_str deserialize_string deserialize_bytes
+        deserialize_byte_buf deserialize_identifier
+    }
+}
+
+struct PairsAccess<'de> {
+    pairs: Iter<'de, (String, String)>,
+    value: Option<&'de str>,
+}
+
+impl<'de> MapAccess<'de> for PairsAccess<'de> {
+    type Error = Error;
+
+    fn next_key_seed<K: DeserializeSeed<'de>>(
+        &mut self,
+        seed: K,
+    ) -> Result<Option<K::Value>, Error> {
+        match self.pairs.next() {
+            Some((key, value)) => {
+                self.value = Some(value);
+                seed.deserialize(ValueDeserializer(key)).map(Some)
+            }
+            None => Ok(None),
+        }
+    }
+
+    fn next_value_seed<V: DeserializeSeed<'de>>(&mut self, seed: V) -> Result<V::Value, Error> {
+        match self.value.take() {
+            Some(value) => seed.deserialize(ValueDeserializer(value)),
+            None => Err(de::Error::custom("value requested before key")),
+        }
+    }
+
+    fn size_hint(&self) -> Option<usize> {
+        Some(self.pairs.len())
+    }
+}
+
+struct ValuesAccess<'de>(Iter<'de, (String, String)>);
+
+impl<'de> SeqAccess<'de> for ValuesAccess<'de> {
+    type Error = Error;
+
+    fn next_element_seed<T: DeserializeSeed<'de>>(
+        &mut self,
+        seed: T,
+    ) -> Result<Option<T::Value>, Error> {
+        match self.0.next() {
+            Some((_, value)) => seed.deserialize(ValueDeserializer(value)).map(Some),
+            None => Ok(None),
+        }
+    }
+
+    fn size_hint(&self) -> Option<usize> {
+        Some(self.0.len())
+    }
+}
+
+/// Deserializes a single string value, parsing it as whichever primitive the target type expects.
+struct ValueDeserializer<'de>(&'de str);
+
+macro_rules! deserialize_parsed {
+    ($($method:ident => $visit:ident,)*) => {$(
+        fn $method<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+            match self.0.parse() {
+                Ok(value) => visitor.$visit(value),
+                Err(_) => Err(de::Error::invalid_value(Unexpected::Str(self.0), &visitor)),
+            }
+        }
+    )*};
+}
+
+impl<'de> Deserializer<'de> for ValueDeserializer<'de> {
+    type Error = Error;
+
+    fn deserialize_any<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+        visitor.visit_borrowed_str(self.0)
+    }
+
+    fn deserialize_bytes<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+        visitor.visit_borrowed_bytes(self.0.as_bytes())
+    }
+
+    fn deserialize_byte_buf<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+        self.deserialize_bytes(visitor)
+    }
+
+    fn deserialize_option<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+        visitor.visit_some(self)
+    }
+
+    fn deserialize_unit<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+        visitor.visit_unit()
+    }
+
+    fn deserialize_newtype_struct<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        visitor: V,
+    ) -> Result<V::Value, Error> {
+        visitor.visit_newtype_struct(self)
+    }
+
+    fn deserialize_enum<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        _variants: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, Error> {
+        visitor.visit_enum(self.0.into_deserializer())
+    }
+
+    deserialize_parsed! {
+        deserialize_bool => visit_bool,
+        deserialize_i8 => visit_i8,
+        deserialize_i16 => visit_i16,
+        deserialize_i32 => visit_i32,
+        deserialize_i64 => visit_i64,
+        deserialize_u8 => visit_u8,
+        deserialize_u16 => visit_u16,
+        deserialize_u32 => visit_u32,
+        deserialize_u64 => visit_u64,
+        deserialize_f32 => visit_f32,
+        deserialize_f64 => visit_f64,
+    }
+
+    forward_to_deserialize_any! {
+        char str string unit_struct seq tuple tuple_struct map struct identifier ignored_any
+    }
+}
+
+#[test]
+fn deserializes_pairs() {
+    use serde::Deserialize;
+
+    #[derive(Debug, PartialEq, Deserialize)]
+    struct Params {
+        org: String,
+        id: u64,
+        active: Option<bool>,
+    }
+
+    let pairs = vec![
+        ("org".to_string(), "acme".to_string()),
+        ("id".to_string(), "42".to_string()),
+    ];
+
+    let params = Params::deserialize(PairsDeserializer::new(&pairs)).unwrap();
+    assert_eq!(
+        params,
+        Params {
+            org: "acme".into(),
+            id: 42,
+            active: None,
+        }
+    );
+
+    let tuple = <(String, u64)>::deserialize(PairsDeserializer::new(&pairs)).unwrap();
+    assert_eq!(tuple, ("acme".to_string(), 42));
+
+    let single = u64::deserialize(PairsDeserializer::new(&pairs[1..])).unwrap();
+    assert_eq!(single, 42);
+
+    assert!(u64::deserialize(PairsDeserializer::new(&pairs)).is_err());
+    assert!(<(u64,)>::deserialize(PairsDeserializer::new(&pairs)).is_err());
+    assert!(u64::deserialize(PairsDeserializer::new(&pairs[..1])).is_err());
+}
@@ -0,0 +1,418 @@
+//! Typed extractors for `Router` handlers.
+//!
+//! Rather than pulling values out of the `Request` and `RouteContext` by hand, a handler can take
+//! any number of arguments implementing [`FromRequest`] and be registered with one of the
+//! `Router`'s `*_async` methods through [`handler`]. Should an extractor reject the request, the
+//! handler isn't called and the [`Rejection`] is returned as an error response instead.
+//!
+//! ```no_run
+//! use serde::Deserialize;
+//! use worker::extract::{handler, Json, Path, State};
+//! use worker::{Response, Result, Router};
+//!
+//! #[derive(Deserialize)]
+//! struct Rename {
+//!     name: String,
+//! }
+//!
+//! async fn rename(
+//!     Path((org, id)): Path<(String, u64)>,
+//!     State(prefix): State<&'static str>,
+//!     Json(body): Json<Rename>,
+//! ) -> Result<Response> {
+//!     Response::ok(format!("{prefix}: renamed user {id} of {org} to {}", body.name))
+//! }
+//!
+//! # fn example() -> Router<'static, &'static str> {
+//! Router::with_data("users").put_async("/orgs/:org/users/:id", handler(rename))
+//! # }
+//! ```
+//!
+//! Extractors which read the request body, such as [`Json`] and [`Form`], can only be used once
+//! per handler.
+
+use std::{
+    fmt::{self, Display},
+    future::Future,
+    ops::{Deref, DerefMut},
+    result::Result as StdResult,
+};
+
+use async_trait::async_trait;
+use futures_util::future::LocalBoxFuture;
+use serde::{de::DeserializeOwned, Serialize};
+use serde_json::error::Category;
+use url::{form_urlencoded, Url};
+
+use crate::{
+    Cf, Env, Error, FormData, Headers, IntoResponse, Method, Request, Response, Result,
+    RouteContext, RouteParams,
+};
+
+use self::de::PairsDeserializer;
+
+mod de;
+
+/// Types that can be created from an incoming request, and used as arguments of a handler
+/// registered through [`handler`].
+#[async_trait(?Send)]
+pub trait FromRequest<D>: Sized {
+    /// Build the extractor from the request and its route context, or reject the request.
+    async fn from_request(req: &mut Request, ctx: &RouteContext<D>) -> StdResult<Self, Rejection>;
+}
+
+/// The reason an extractor could not be created from a request, returned to the client as an
+/// error response with the rejection's status code and message.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Rejection {
+    status: u16,
+    message: String,
+}
+
+impl Rejection {
+    /// Create a new `Rejection`. A status code which isn't an error status in the range
+    /// `400..=599` is replaced with `500 Internal Server Error`.
+    pub fn new(status: u16, message: impl Into<String>) -> Self {
+        let status = if (400..=599).contains(&status) {
+            status
+        } else {
+            500
+        };
+        Self {
+            status,
+            message: message.into(),
+        }
+    }
+
+    /// The HTTP status code the request will be rejected with.
+    pub fn status(&self) -> u16 {
+        self.status
+    }
+
+    /// The message used as the body of the error response.
+    pub fn message(&self) -> &str {
+        &self.message
+    }
+
+    fn from_body_error(error: Error) -> Self {
+        match error {
+            Error::BodyUsed => error.into(),
+            error => Self::new(400, format!("Failed to read request body: {error}")),
+        }
+    }
+}
+
+impl Display for Rejection {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} (status: {})", self.message, self.status)
+    }
+}
+
+impl std::error::Error for Rejection {}
+
+/// Responds with a plaintext error `Response`.
+impl IntoResponse for Rejection {
+    fn into_response(self) -> Result<Response> {
+        Response::error(self.message, self.status)
+    }
+}
+
+impl From<Error> for Rejection {
+    fn from(error: Error) -> Self {
+        Self::new(500, error.to_string())
+    }
+}
+
+macro_rules! wrapper {
+    ($($(#[$attr:meta])* $name:ident,)*) => {$(
+        $(#[$attr])*
+        #[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+        pub struct $name<T>(pub T);
+
+        impl<T> $name<T> {
+            /// Consume the extractor, returning the wrapped value.
+            pub fn into_inner(self) -> T {
+                self.0
+            }
+        }
+
+        impl<T> Deref for $name<T> {
+            type Target = T;
+
+            fn deref(&self) -> &T {
+                &self.0
+            }
+        }
+
+        impl<T> DerefMut for $name<T> {
+            fn deref_mut(&mut self) -> &mut T {
+                &mut self.0
+            }
+        }
+    )*};
+}
+
+wrapper! {
+    /// Extracts the URL parameters matched by the route's pattern. Parameters can be deserialized
+    /// by name into a struct or map, by position into a tuple such as `Path<(String, u64)>`, or
+    /// into a single value if the pattern has exactly one parameter. Parameters captured by the
+    /// prefix of a mounted `Router` can only be deserialized by name.
+    ///
+    /// Rejects the request with `400 Bad Request` if the parameters can't be deserialized.
+    Path,
+    /// Extracts the URL's query string, deserialized into a struct or map.
+    ///
+    /// Rejects the request with `400 Bad Request` if the query string can't be deserialized.
+    Query,
+    /// Extracts a request body encoded as JSON. Can also be returned from a handler to respond with
+    /// the wrapped value serialized as JSON.
+    ///
+    /// Rejects the request with `415 Unsupported Media Type` if it isn't sent with an
+    /// `application/json` content type, `400 Bad Request` if the body isn't valid JSON, and
+    /// `422 Unprocessable Entity` if the JSON doesn't match the expected type.
+    Json,
+    /// Extracts a request body encoded as `application/x-www-form-urlencoded`. Use [`FormData`]
+    /// as an extractor to handle `multipart/form-data` bodies and file uploads.
+    ///
+    /// Rejects the request with `415 Unsupported Media Type` if it is sent with a different
+    /// content type, and `422 Unprocessable Entity` if the form doesn't match the expected type.
+    Form,
+    /// Extracts a clone of the data provided to the `Router`.
+    State,
+}
+
+impl<T: DeserializeOwned> Path<T> {
+    pub(crate) fn from_params(params: &RouteParams) -> StdResult<Self, Rejection> {
+        T::deserialize(PairsDeserializer::new(params.as_slice()).with_positional(params.own()))
+            .map(Path)
+            .map_err(|e| Rejection::new(400, format!("Invalid URL parameters: {e}")))
+    }
+}
+
+#[async_trait(?Send)]
+impl<D, T: DeserializeOwned> FromRequest<D> for Path<T> {
+    async fn from_request(_: &mut Request, ctx: &RouteContext<D>) -> StdResult<Self, Rejection> {
+        Path::from_params(ctx.params())
+    }
+}
+
+#[async_trait(?Send)]
+impl<D, T: DeserializeOwned> FromRequest<D> for Query<T> {
+    async fn from_request(req: &mut Request, _: &RouteContext<D>) -> StdResult<Self, Rejection> {
+        let url = req.url()?;
+        let pairs: Vec<_> = url.query_pairs().into_owned().collect();
+        T::deserialize(PairsDeserializer::new(&pairs))
+            .map(Query)
+            .map_err(|e| Rejection::new(400, format!("Invalid query string: {e}")))
+    }
+}
+
+#[async_trait(?Send)]
+impl<D, T: DeserializeOwned> FromRequest<D> for Json<T> {
+    async fn from_request(req: &mut Request, _: &RouteContext<D>) -> StdResult<Self, Rejection> {
+        let is_json = match media_type(req)? {
+            Some(media_type) => {
+                media_type == "application/json"
+                    || (media_type.starts_with("application/") && media_type.ends_with("+json"))
+            }
+            None => false,
+        };
+        if !is_json {
+            return Err(Rejection::new(
+                415,
+                "Expected request with `Content-Type: application/json`",
+            ));
+        }
+
+        let body = req.text().await.map_err(Rejection::from_body_error)?;
+        let mut de = serde_json::Deserializer::from_str(&body);
+        T::deserialize(&mut de)
+            .and_then(|value| de.end().map(|_| value))
+            .map(Json)
+            .map_err(|e| match e.classify() {
+                Category::Data => Rejection::new(
+                    422,
+                    format!("Failed to deserialize the JSON body into the target type: {e}"),
+                ),
+                _ => Rejection::new(
+                    400,
+                    format!("Failed to parse the request body as JSON: {e}"),
+                ),
+            })
+    }
+}
+
+impl<T: Serialize> IntoResponse for Json<T> {
+    fn into_response(self) -> Result<Response> {
+        Response::from_json(&self.0)
+    }
+}
+
+#[async_trait(?Send)]
+impl<D, T: DeserializeOwned> FromRequest<D> for Form<T> {
+    async fn from_request(req: &mut Request, _: &RouteContext<D>) -> StdResult<Self, Rejection> {
+        if media_type(req)?.as_deref() != Some("application/x-www-form-urlencoded") {
+            return Err(Rejection::new(
+                415,
+                "Expected request with `Content-Type: application/x-www-form-urlencoded`",
+            ));
+        }
+
+        let body = req.text().await.map_err(Rejection::from_body_error)?;
+        let pairs: Vec<_> = form_urlencoded::parse(body.as_bytes())
+            .into_owned()
+            .collect();
+        T::deserialize(PairsDeserializer::new(&pairs))
+            .map(Form)
+            .map_err(|e| Rejection::new(422, format!("Failed to deserialize form body: {e}")))
+    }
+}
+
+#[async_trait(?Send)]
+impl<D: Clone> FromRequest<D> for State<D> {
+    async fn from_request(_: &mut Request, ctx: &RouteContext<D>) -> StdResult<Self, Rejection> {
+        Ok(State(ctx.data.clone()))
+    }
+}
+
+#[async_trait(?Send)]
+impl<D> FromRequest<D> for FormData {
+    async fn from_request(req: &mut Request, _: &RouteContext<D>) -> StdResult<Self, Rejection> {
+        match media_type(req)?.as_deref() {
+            Some("multipart/form-data" | "application/x-www-form-urlencoded") => {
+                req.form_data().await.map_err(Rejection::from_body_error)
+            }
+            _ => Err(Rejection::new(
+                415,
+                "Expected request with `Content-Type: multipart/form-data`",
+            )),
+        }
+    }
+}
+
+#[async_trait(?Send)]
+impl<D> FromRequest<D> for Headers {
+    async fn from_request(req: &mut Request, _: &RouteContext<D>) -> StdResult<Self, Rejection> {
+        Ok(req.headers().clone())
+    }
+}
+
+#[async_trait(?Send)]
+impl<D> FromRequest<D> for Cf {
+    async fn from_request(req: &mut Request, _: &RouteContext<D>) -> StdResult<Self, Rejection> {
+        Ok(req.cf().clone())
+    }
+}
+
+#[async_trait(?Send)]
+impl<D> FromRequest<D> for Method {
+    async fn from_request(req: &mut Request, _: &RouteContext<D>) -> StdResult<Self, Rejection> {
+        Ok(req.method())
+    }
+}
+
+#[async_trait(?Send)]
+impl<D> FromRequest<D> for Url {
+    async fn from_request(req: &mut Request, _: &RouteContext<D>) -> StdResult<Self, Rejection> {
+        Ok(req.url()?)
+    }
+}
+
+#[async_trait(?Send)]
+impl<D> FromRequest<D> for Env {
+    async fn from_request(_: &mut Request, ctx: &RouteContext<D>) -> StdResult<Self, Rejection> {
+        Ok(ctx.env.clone())
+    }
+}
+
+/// Makes an extractor optional, yielding `None` instead of rejecting the request when what it
+/// extracts is missing or invalid, i.e. for `4xx` rejections. Server errors, such as a body which
+/// was already read, still reject the request.
+#[async_trait(?Send)]
+impl<D, T: FromRequest<D>> FromRequest<D> for Option<T> {
+    async fn from_request(req: &mut Request, ctx: &RouteContext<D>) -> StdResult<Self, Rejection> {
+        match T::from_request(req, ctx).await {
+            Ok(value) => Ok(Some(value)),
+            Err(rejection) if rejection.status < 500 => Ok(None),
+            Err(rejection) => Err(rejection),
+        }
+    }
+}
+
+/// Hands the extractor's rejection to the handler, instead of responding with it directly.
+#[async_trait(?Send)]
+impl<D, T: FromRequest<D>> FromRequest<D> for StdResult<T, Rejection> {
+    async fn from_request(req: &mut Request, ctx: &RouteContext<D>) -> StdResult<Self, Rejection> {
+        Ok(T::from_request(req, ctx).await)
+    }
+}
+
+/// The lowercased media type of the request's `Content-Type` header, without any parameters.
+fn media_type(req: &Request) -> StdResult<Option<String>, Rejection> {
+    Ok(req.headers().get("content-type")?.map(|content_type| {
+        let media_type = content_type.split(';').next().unwrap_or_default();
+        media_type.trim().to_ascii_lowercase()
+    }))
+}
+
+/// A function whose arguments are all extractors, which can be adapted into a `Router` handler
+/// with [`handler`]. Implemented for functions and closures taking up to eight extractors.
+pub trait ExtractorHandler<'a, D, Args>: Clone + 'a {
+    /// Extract the arguments from the request, then call the function with them.
+    fn call(self, req: Request, ctx: RouteContext<D>) -> LocalBoxFuture<'a, Result<Response>>;
+}
+
+macro_rules! impl_extractor_handler {
+    ($($ty:ident),*) => {
+        impl<'a, D, F, Fut, $($ty,)*> ExtractorHandler<'a, D, ($($ty,)*)> for F
+        where
+            D: 'a,
+            F: FnOnce($($ty),*) -> Fut + Clone + 'a,
+            Fut: Future + 'a,
+            Fut::Output: IntoResponse,
+            $($ty: FromRequest<D> + 'a,)*
+        {
+            #[allow(non_snake_case, unused_mut, unused_variables)]
+            fn call(
+                self,
+                mut req: Request,
+                ctx: RouteContext<D>,
+            ) -> LocalBoxFuture<'a, Result<Response>> {
+                Box::pin(async move {
+                    $(
+                        let $ty = match $ty::from_request(&mut req, &ctx).await {
+                            Ok(value) => value,
+                            Err(rejection) => return rejection.into_response(),
+                        };
+                    )*
+                    self($($ty),*).await.into_response()
+                })
+            }
+        }
+    };
+}
+
+impl_extractor_handler!();
+impl_extractor_handler!(T1);
+impl_extractor_handler!(T1, T2);
+impl_extractor_handler!(T1, T2, T3);
+impl_extractor_handler!(T1, T2, T3, T4);
+impl_extractor_handler!(T1, T2, T3, T4, T5);
+impl_extractor_handler!(T1, T2, T3, T4, T5, T6);
+impl_extractor_handler!(T1, T2, T3, T4, T5, T6, T7);
+impl_extractor_handler!(T1, T2, T3, T4, T5, T6, T7, T8);
+
+/// Adapt a function taking extractors as its arguments into a handler for the `Router`'s
+/// `*_async` methods, e.g. `router.get_async("/users/:id", handler(get_user))`.
+pub fn handler<'a, D: 'a, Args, H: ExtractorHandler<'a, D, Args>>(
+    handler: H,
+) -> impl Fn(Request, RouteContext<D>) -> LocalBoxFuture<'a, Result<Response>> + 'a {
+    move |req, ctx| handler.clone().call(req, ctx)
+}
+
+#[test]
+fn rejection_requires_error_status() {
+    assert_eq!(Rejection::new(404, "not found").status(), 404);
+    assert_eq!(Rejection::new(200, "ok").status(), 500);
+    assert_eq!(Rejection::new(600, "unknown").status(), 500);
+}
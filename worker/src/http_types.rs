@@ -1,19 +1,21 @@
 use std::{
-    convert::TryFrom,
+    convert::{TryFrom, TryInto},
     pin::Pin,
     task::{Context, Poll},
 };
 
 use bytes::Buf;
 use futures_util::Stream;
-use http::{HeaderMap, Request, Response};
+use http::{header::HeaderName, HeaderMap, HeaderValue, Request, Response};
 use http_body::Body as HttpBody;
 use js_sys::Uint8Array;
 use wasm_bindgen::{JsCast, JsValue};
 use wasm_streams::ReadableStream;
+use worker_sys::Response as EdgeResponse;
 
 use crate::{
-    ByteStream, Cf, Error, Response as WorkerResponse, ResponseBody as WorkerResponseBody,
+    ByteStream, Cf, Error, Headers, Request as WorkerRequest, Response as WorkerResponse,
+    ResponseBody as WorkerResponseBody,
 };
 
 pub use ::http as http_types;
@@ -24,11 +26,11 @@ impl TryFrom<EdgeRequest> for crate::HttpRequest {
     type Error = Error;
 
     fn try_from(e: EdgeRequest) -> Result<Self, Self::Error> {
-        let headers: HeaderMap = crate::Headers(e.0.headers()).into();
+        let headers = to_header_map(&Headers(e.0.headers()))?;
 
         let body =
             e.0.body()
-                .map(|stream| ReadableStream::from_raw(stream.dyn_into().unwrap()))
+                .map(|stream| ReadableStream::from_raw(stream.unchecked_into()))
                 .unwrap_or_else(|| {
                     wasm_streams::ReadableStream::from_stream(futures_util::stream::empty())
                 });
@@ -38,9 +40,9 @@ impl TryFrom<EdgeRequest> for crate::HttpRequest {
 
         let mut request = Request::new(body);
         *request.headers_mut() = headers;
-        *request.method_mut() = http::Method::from_bytes(e.0.method().as_bytes())
-            .expect("unable to parse request method");
-        *request.uri_mut() = e.0.url().parse().expect("unable to parse url");
+        *request.method_mut() =
+            http::Method::from_bytes(e.0.method().as_bytes()).map_err(http::Error::from)?;
+        *request.uri_mut() = e.0.url().parse().map_err(http::Error::from)?;
 
         request.extensions_mut().insert(Cf::from(e.0.cf()));
 
@@ -48,6 +50,38 @@ impl TryFrom<EdgeRequest> for crate::HttpRequest {
     }
 }
 
+impl TryFrom<WorkerRequest> for crate::HttpRequest {
+    type Error = Error;
+
+    fn try_from(req: WorkerRequest) -> Result<Self, Self::Error> {
+        let req = worker_sys::Request::try_from(req)?;
+        EdgeRequest(req).try_into()
+    }
+}
+
+impl TryFrom<WorkerResponse> for Response<ByteStream> {
+    type Error = Error;
+
+    fn try_from(resp: WorkerResponse) -> Result<Self, Self::Error> {
+        let resp = EdgeResponse::from(resp);
+
+        let body = resp
+            .body()
+            .map(|stream| ReadableStream::from_raw(stream.unchecked_into()))
+            .unwrap_or_else(|| {
+                wasm_streams::ReadableStream::from_stream(futures_util::stream::empty())
+            });
+
+        let mut response = Response::new(ByteStream {
+            inner: body.into_stream(),
+        });
+        *response.status_mut() = to_status_code(resp.status())?;
+        *response.headers_mut() = to_header_map(&Headers(resp.headers()))?;
+
+        Ok(response)
+    }
+}
+
 impl<B> TryFrom<Response<B>> for WorkerResponse
 where
     B: HttpBody + 'static,
@@ -62,13 +96,50 @@ where
         let resp_body = WorkerResponseBody::Stream(body_stream.into_raw().unchecked_into());
 
         let resp = WorkerResponse::from_body(resp_body)?
-            .with_headers(parts.headers.into())
+            .with_headers(from_header_map(&parts.headers)?)
             .with_status(parts.status.as_u16());
 
         Ok(resp)
     }
 }
 
+// Header values are byte strings in JS, where each character holds a single byte of the value,
+// so they are converted byte by byte rather than as UTF-8. This lets values with non-ASCII bytes
+// pass through, where `HeaderValue::to_str` would refuse them.
+fn to_header_map(headers: impl IntoIterator<Item = (String, String)>) -> Result<HeaderMap, Error> {
+    headers
+        .into_iter()
+        .map(|(name, value)| {
+            let name = HeaderName::from_bytes(name.as_bytes()).map_err(http::Error::from)?;
+            Ok((name, to_header_value(&value)?))
+        })
+        .collect()
+}
+
+fn to_status_code(status: u16) -> Result<http::StatusCode, Error> {
+    Ok(http::StatusCode::from_u16(status).map_err(http::Error::from)?)
+}
+
+fn to_header_value(value: &str) -> Result<HeaderValue, Error> {
+    let bytes = value
+        .chars()
+        .map(|c| u8::try_from(u32::from(c)))
+        .collect::<Result<Vec<u8>, _>>()
+        .map_err(|_| Error::RustError(format!("header value is not a byte string: {value}")))?;
+
+    Ok(HeaderValue::from_bytes(&bytes).map_err(http::Error::from)?)
+}
+
+fn from_header_map(map: &HeaderMap) -> Result<Headers, Error> {
+    let mut headers = Headers::new();
+    for (name, value) in map {
+        let value: String = value.as_bytes().iter().copied().map(char::from).collect();
+        headers.append(name.as_str(), &value)?;
+    }
+
+    Ok(headers)
+}
+
 #[pin_project::pin_project]
 struct BodyStream<B: HttpBody> {
     #[pin]
@@ -139,3 +210,27 @@ fn advance_data<D: Buf>(mut data: D) -> (D, Option<Result<JsValue, JsValue>>) {
 
     (data, next_chunk)
 }
+
+#[test]
+fn header_values_are_byte_strings() {
+    assert_eq!(to_header_value("text/plain").unwrap(), "text/plain");
+    assert_eq!(to_header_value("caf\u{e9}").unwrap().as_bytes(), b"caf\xe9");
+    assert!(to_header_value("\u{2603}").is_err());
+    assert!(to_header_value("a\u{1}b").is_err());
+}
+
+#[test]
+fn response_parts_are_validated() {
+    assert_eq!(to_status_code(204).unwrap(), http::StatusCode::NO_CONTENT);
+    assert!(to_status_code(1000).is_err());
+
+    let headers = to_header_map(vec![
+        ("content-type".to_string(), "text/plain".to_string()),
+        ("x-name".to_string(), "caf\u{e9}".to_string()),
+    ])
+    .unwrap();
+    assert_eq!(headers["content-type"], "text/plain");
+    assert_eq!(headers["x-name"].as_bytes(), b"caf\xe9");
+    assert!(to_header_map(vec![("bad name".to_string(), "a".to_string())]).is_err());
+    assert!(to_header_map(vec![("x-bad".to_string(), "a\u{1}b".to_string())]).is_err());
+}
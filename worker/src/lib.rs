@@ -16,7 +16,7 @@ pub use wasm_bindgen_futures;
 pub use worker_kv as kv;
 
 pub use cf::Cf;
-pub use worker_macros::{durable_object, event};
+pub use worker_macros::{durable_object, durable_object_rpc, event};
 #[doc(hidden)]
 pub use worker_sys;
 pub use worker_sys::{console_debug, console_error, console_log, console_warn};
@@ -38,14 +38,16 @@ pub use crate::formdata::*;
 pub use crate::global::Fetch;
 pub use crate::headers::Headers;
 pub use crate::method::Method;
+pub use crate::problem_details::ProblemDetails;
 #[cfg(feature = "queue")]
 pub use crate::queue::*;
 pub use crate::r2::*;
 pub use crate::request::Request;
 pub use crate::request_init::*;
-pub use crate::response::{Response, ResponseBody};
-pub use crate::router::{RouteContext, RouteParams, Router};
+pub use crate::response::{IntoResponse, Response, ResponseBody};
+pub use crate::router::{Next, RouteContext, RouteParams, Router};
 pub use crate::schedule::*;
+pub use crate::service::{serve, WorkerExtensions};
 pub use crate::streams::*;
 pub use crate::websocket::*;
 
@@ -62,10 +64,12 @@ pub mod durable;
 mod dynamic_dispatch;
 mod env;
 mod error;
+pub mod extract;
 mod fetcher;
 mod formdata;
 mod global;
 mod headers;
+mod problem_details;
 #[cfg(feature = "queue")]
 mod queue;
 mod r2;
@@ -76,6 +80,7 @@ mod request_init;
 mod response;
 mod router;
 mod schedule;
+mod service;
 mod streams;
 mod websocket;
 
@@ -0,0 +1,117 @@
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+
+use crate::{Error, IntoResponse, Response, Result};
+
+const CONTENT_TYPE: &str = "application/problem+json";
+
+/// A machine-readable description of an error, following the "Problem Details for HTTP APIs"
+/// format of [RFC 7807](https://www.rfc-editor.org/rfc/rfc7807). Responds with the
+/// `application/problem+json` content type when returned from a handler.
+///
+/// An `Error` can be converted into problem details using its status code hint, which makes
+/// `ProblemDetails::from` a convenient error handler for the `Router` and `#[event(fetch)]`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ProblemDetails {
+    /// A URI reference identifying the problem type, `about:blank` when omitted.
+    #[serde(rename = "type", skip_serializing_if = "Option::is_none")]
+    pub type_: Option<String>,
+    /// A short, human-readable summary of the problem type.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub title: Option<String>,
+    /// The HTTP status code of the response.
+    pub status: u16,
+    /// A human-readable explanation specific to this occurrence of the problem.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub detail: Option<String>,
+    /// A URI reference identifying this specific occurrence of the problem.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub instance: Option<String>,
+    /// Additional members describing the problem.
+    #[serde(flatten)]
+    pub extensions: Map<String, Value>,
+}
+
+impl ProblemDetails {
+    /// Create problem details for the status code, titled with the status code's reason phrase.
+    pub fn new(status: u16) -> Self {
+        let title = http::StatusCode::from_u16(status)
+            .ok()
+            .and_then(|status| status.canonical_reason())
+            .map(String::from);
+
+        Self {
+            type_: None,
+            title,
+            status,
+            detail: None,
+            instance: None,
+            extensions: Map::new(),
+        }
+    }
+
+    /// Set the URI reference identifying the problem type.
+    pub fn with_type(mut self, type_: impl Into<String>) -> Self {
+        self.type_ = Some(type_.into());
+        self
+    }
+
+    /// Set the human-readable summary of the problem type.
+    pub fn with_title(mut self, title: impl Into<String>) -> Self {
+        self.title = Some(title.into());
+        self
+    }
+
+    /// Set the human-readable explanation of this occurrence of the problem.
+    pub fn with_detail(mut self, detail: impl Into<String>) -> Self {
+        self.detail = Some(detail.into());
+        self
+    }
+
+    /// Set the URI reference identifying this occurrence of the problem.
+    pub fn with_instance(mut self, instance: impl Into<String>) -> Self {
+        self.instance = Some(instance.into());
+        self
+    }
+
+    /// Add an extension member, such as a list of the invalid fields in a request.
+    pub fn with_extension(mut self, name: impl Into<String>, value: impl Into<Value>) -> Self {
+        self.extensions.insert(name.into(), value.into());
+        self
+    }
+}
+
+impl From<&Error> for ProblemDetails {
+    fn from(error: &Error) -> Self {
+        Self::new(error.status_code()).with_detail(error.to_string())
+    }
+}
+
+impl From<Error> for ProblemDetails {
+    fn from(error: Error) -> Self {
+        Self::from(&error)
+    }
+}
+
+impl IntoResponse for ProblemDetails {
+    fn into_response(self) -> Result<Response> {
+        let mut resp = Response::from_json(&self)?.with_status(self.status);
+        resp.headers_mut().set("content-type", CONTENT_TYPE)?;
+        Ok(resp)
+    }
+}
+
+#[test]
+fn serializes_problem_details() {
+    let problem = ProblemDetails::from(Error::BadEncoding).with_extension("field", "body");
+
+    assert_eq!(
+        serde_json::to_value(&problem).unwrap(),
+        serde_json::json!({
+            "title": "Unsupported Media Type",
+            "status": 415,
+            "detail": "content-type mismatch",
+            "field": "body",
+        })
+    );
+}
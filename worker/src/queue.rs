@@ -1,37 +1,26 @@
-use std::marker::PhantomData;
+use std::{future::Future, marker::PhantomData, ops::Range};
 
 use crate::{env::EnvBinding, Date, Error, Result};
-use js_sys::Array;
+use futures_util::{stream, StreamExt};
+use js_sys::{Array, ArrayBuffer, Uint8Array};
 use serde::{de::DeserializeOwned, Serialize};
+use serde_wasm_bindgen::Serializer;
 use wasm_bindgen::{prelude::*, JsCast};
 use wasm_bindgen_futures::JsFuture;
-use worker_sys::{MessageBatch as MessageBatchSys, Queue as EdgeQueue};
-
-static BODY_KEY_STR: &str = "body";
-static ID_KEY_STR: &str = "id";
-static TIMESTAMP_KEY_STR: &str = "timestamp";
+use worker_sys::{Message as MessageSys, MessageBatch as MessageBatchSys, Queue as EdgeQueue};
 
 pub struct MessageBatch<T> {
     inner: MessageBatchSys,
     messages: Array,
     data: PhantomData<T>,
-    timestamp_key: JsValue,
-    body_key: JsValue,
-    id_key: JsValue,
 }
 
 impl<T> MessageBatch<T> {
     pub fn new(message_batch_sys: MessageBatchSys) -> Self {
-        let timestamp_key = JsValue::from_str(TIMESTAMP_KEY_STR);
-        let body_key = JsValue::from_str(BODY_KEY_STR);
-        let id_key = JsValue::from_str(ID_KEY_STR);
         Self {
             messages: message_batch_sys.messages(),
             inner: message_batch_sys,
             data: PhantomData,
-            timestamp_key,
-            body_key,
-            id_key,
         }
     }
 }
@@ -40,6 +29,151 @@ pub struct Message<T> {
     pub body: T,
     pub timestamp: Date,
     pub id: String,
+    /// The number of times delivery of this message has been attempted, including this one.
+    pub attempts: u32,
+    inner: MessageSys,
+}
+
+impl<T> Message<T> {
+    /// Marks this message as successfully delivered, so it won't be redelivered even if the
+    /// handler then fails or retries the rest of the batch.
+    pub fn ack(&self) {
+        self.inner.ack();
+    }
+
+    /// Marks this message to be retried in a later batch, even if the handler succeeds.
+    pub fn retry(&self) {
+        self.inner.retry();
+    }
+
+    /// Marks this message to be retried in a later batch, e.g. only after a delay.
+    pub fn retry_with_options(&self, options: QueueRetryOptions) -> Result<()> {
+        self.inner
+            .retry_with_options(serde_wasm_bindgen::to_value(&options)?);
+        Ok(())
+    }
+}
+
+/// A message whose body hasn't been deserialized, from [`MessageBatch::raw_iter`].
+///
+/// This lets a consumer accept bodies which aren't serde values, such as text or bytes, or
+/// messages of different types in the same Queue. Tagging those with a field and deserializing
+/// them into an internally tagged enum means a message of an unknown type only fails on its own,
+/// and can be retried or acked without affecting the rest of the batch:
+///
+/// ```no_run
+/// # use worker::*;
+/// #[derive(serde::Deserialize)]
+/// #[serde(tag = "type", rename_all = "snake_case")]
+/// enum Event {
+///     SignUp { user: String },
+///     Purchase { user: String, amount: u64 },
+/// }
+///
+/// # async fn example(message_batch: MessageBatch<Event>) -> Result<()> {
+/// for message in message_batch.raw_iter() {
+///     match message.deserialize::<Event>() {
+///         Ok(Event::SignUp { user }) => console_log!("{} signed up", user),
+///         Ok(Event::Purchase { user, amount }) => console_log!("{} spent {}", user, amount),
+///         Err(e) => {
+///             console_error!("Unexpected message {}: {}", message.id, e);
+///             message.ack();
+///         }
+///     }
+/// }
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Clone)]
+pub struct RawMessage {
+    pub timestamp: Date,
+    pub id: String,
+    /// The number of times delivery of this message has been attempted, including this one.
+    pub attempts: u32,
+    inner: MessageSys,
+}
+
+impl RawMessage {
+    fn new(message: MessageSys) -> Self {
+        Self {
+            id: message.id(),
+            timestamp: Date::from(message.timestamp()),
+            // runtimes which don't count attempts only ever deliver a message once
+            attempts: message.attempts().unwrap_or(1),
+            inner: message,
+        }
+    }
+
+    /// The body as it was delivered.
+    pub fn body(&self) -> JsValue {
+        self.inner.body()
+    }
+
+    /// The body of a message sent as text.
+    pub fn text(&self) -> Result<String> {
+        self.body()
+            .as_string()
+            .ok_or_else(|| Error::RustError(format!("body of message {} isn't text", self.id)))
+    }
+
+    /// The body of a message sent as bytes.
+    pub fn bytes(&self) -> Result<Vec<u8>> {
+        let body = self.body();
+        if let Some(bytes) = body.dyn_ref::<Uint8Array>() {
+            Ok(bytes.to_vec())
+        } else if let Some(buffer) = body.dyn_ref::<ArrayBuffer>() {
+            Ok(Uint8Array::new(buffer).to_vec())
+        } else {
+            Err(Error::RustError(format!(
+                "body of message {} isn't bytes",
+                self.id
+            )))
+        }
+    }
+
+    /// Deserializes the body, which needn't be of the batch's type.
+    pub fn deserialize<T: DeserializeOwned>(&self) -> Result<T> {
+        Ok(serde_wasm_bindgen::from_value(self.body())?)
+    }
+
+    /// Deserializes the body into a [`Message`].
+    pub fn into_message<T: DeserializeOwned>(self) -> Result<Message<T>> {
+        Ok(Message {
+            body: self.deserialize()?,
+            timestamp: self.timestamp,
+            id: self.id,
+            attempts: self.attempts,
+            inner: self.inner,
+        })
+    }
+
+    /// Marks this message as successfully delivered, so it won't be redelivered even if the
+    /// handler then fails or retries the rest of the batch.
+    pub fn ack(&self) {
+        self.inner.ack();
+    }
+
+    /// Marks this message to be retried in a later batch, even if the handler succeeds.
+    pub fn retry(&self) {
+        self.inner.retry();
+    }
+
+    /// Marks this message to be retried in a later batch, e.g. only after a delay.
+    pub fn retry_with_options(&self, options: QueueRetryOptions) -> Result<()> {
+        self.inner
+            .retry_with_options(serde_wasm_bindgen::to_value(&options)?);
+        Ok(())
+    }
+}
+
+/// Options for retrying messages with [`Message::retry_with_options`] or
+/// [`MessageBatch::retry_all_with_options`].
+#[derive(Debug, Clone, Default, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct QueueRetryOptions {
+    /// How long to wait before the message is delivered again.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub delay_seconds: Option<u32>,
 }
 
 impl<T> MessageBatch<T> {
@@ -53,6 +187,19 @@ impl<T> MessageBatch<T> {
         self.inner.retry_all();
     }
 
+    /// Marks every message to be retried in a later batch, e.g. only after a delay.
+    pub fn retry_all_with_options(&self, options: QueueRetryOptions) -> Result<()> {
+        self.inner
+            .retry_all_with_options(serde_wasm_bindgen::to_value(&options)?);
+        Ok(())
+    }
+
+    /// Marks every message as successfully delivered, so none are redelivered even if the handler
+    /// then fails.
+    pub fn ack_all(&self) {
+        self.inner.ack_all();
+    }
+
     /// Iterator that deserializes messages in the message batch. Ordering of messages is not guaranteed.
     pub fn iter(&self) -> MessageIter<'_, T>
     where
@@ -61,13 +208,18 @@ impl<T> MessageBatch<T> {
         MessageIter {
             range: 0..self.messages.length(),
             array: &self.messages,
-            timestamp_key: &self.timestamp_key,
-            body_key: &self.body_key,
-            id_key: &self.id_key,
             data: PhantomData,
         }
     }
 
+    /// Iterator over the messages in the batch without deserializing them, so that bodies which
+    /// aren't of the batch's type can be handled message by message. Ordering of messages is not
+    /// guaranteed.
+    pub fn raw_iter(&self) -> impl ExactSizeIterator<Item = RawMessage> + DoubleEndedIterator + '_ {
+        (0..self.messages.length())
+            .map(move |index| RawMessage::new(self.messages.get(index).unchecked_into()))
+    }
+
     /// An array of messages in the batch. Ordering of messages is not guaranteed.
     pub fn messages(&self) -> Result<Vec<Message<T>>>
     where
@@ -75,14 +227,253 @@ impl<T> MessageBatch<T> {
     {
         self.iter().collect()
     }
+
+    /// Runs `handler` on every message, with at most `concurrency` of them at a time. Messages
+    /// are acked once their handler succeeds and retried if it fails or their body can't be
+    /// deserialized.
+    ///
+    /// ```no_run
+    /// # use worker::*;
+    /// # async fn example(message_batch: MessageBatch<String>, env: Env) -> Result<()> {
+    /// let summary = message_batch
+    ///     .process(10, |message| async move {
+    ///         console_log!("Got message {}", message.body);
+    ///         Ok(())
+    ///     })
+    ///     .await;
+    /// console_log!("{} succeeded, {} retried", summary.succeeded, summary.retried);
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub async fn process<F, Fut>(&self, concurrency: usize, handler: F) -> ProcessSummary
+    where
+        T: DeserializeOwned,
+        F: Fn(Message<T>) -> Fut,
+        Fut: Future<Output = Result<()>>,
+    {
+        self.process_with_options(
+            ProcessOptions {
+                concurrency,
+                ..ProcessOptions::default()
+            },
+            handler,
+        )
+        .await
+    }
+
+    /// Runs `handler` on every message like [`process`](Self::process), but with options for
+    /// giving up on messages which keep failing, such as forwarding them to a dead-letter Queue.
+    pub async fn process_with_options<F, Fut>(
+        &self,
+        options: ProcessOptions,
+        handler: F,
+    ) -> ProcessSummary
+    where
+        T: DeserializeOwned,
+        F: Fn(Message<T>) -> Fut,
+        Fut: Future<Output = Result<()>>,
+    {
+        let options = &options;
+        let handler = &handler;
+
+        stream::iter(self.raw_iter())
+            .map(|message| async move {
+                let result = match message.clone().into_message() {
+                    Ok(message) => handler(message).await,
+                    Err(e) => Err(e),
+                };
+                let outcome = match result {
+                    Ok(()) => {
+                        message.ack();
+                        Outcome::Succeeded
+                    }
+                    Err(error) => options.settle_failed(&message, error).await,
+                };
+                (message, outcome)
+            })
+            .buffer_unordered(options.concurrency.max(1))
+            .fold(
+                ProcessSummary::default(),
+                |mut summary, (message, outcome)| {
+                    summary.record(message, outcome);
+                    async move { summary }
+                },
+            )
+            .await
+    }
+}
+
+/// Options for [`MessageBatch::process_with_options`].
+#[derive(Default)]
+pub struct ProcessOptions {
+    /// The most messages whose handlers run at the same time. Values under 1 are treated as 1.
+    pub concurrency: usize,
+    /// How many times delivery of a message is attempted before giving up on it, instead of
+    /// retrying it until the Queue's own limit is reached.
+    pub max_attempts: Option<u32>,
+    /// The Queue messages which have been given up on are forwarded to, with their bodies as they
+    /// were delivered. Without one they are acked and discarded.
+    pub dead_letter_queue: Option<Queue>,
+    /// The options failed messages are retried with.
+    pub retry: QueueRetryOptions,
+}
+
+impl ProcessOptions {
+    async fn settle_failed(&self, message: &RawMessage, error: Error) -> Outcome {
+        let dead_letter = self
+            .dead_letter_queue
+            .as_ref()
+            .map(|queue| move || queue.send_raw(message.body()));
+        settle(message, error, self.max_attempts, &self.retry, dead_letter).await
+    }
+}
+
+/// The parts of a message [`settle`] needs, so that it can be tested without a runtime.
+trait Settle {
+    fn attempts(&self) -> u32;
+    fn ack(&self);
+    fn retry(&self);
+    fn retry_with_options(&self, options: QueueRetryOptions) -> Result<()>;
+}
+
+impl Settle for RawMessage {
+    fn attempts(&self) -> u32 {
+        self.attempts
+    }
+
+    fn ack(&self) {
+        RawMessage::ack(self)
+    }
+
+    fn retry(&self) {
+        RawMessage::retry(self)
+    }
+
+    fn retry_with_options(&self, options: QueueRetryOptions) -> Result<()> {
+        RawMessage::retry_with_options(self, options)
+    }
+}
+
+/// Retries a message whose handler failed with `error`, or gives up on it once it has been
+/// attempted `max_attempts` times, forwarding it with `dead_letter` if there's a dead-letter Queue.
+async fn settle<M, F, Fut>(
+    message: &M,
+    error: Error,
+    max_attempts: Option<u32>,
+    retry: &QueueRetryOptions,
+    dead_letter: Option<F>,
+) -> Outcome
+where
+    M: Settle,
+    F: FnOnce() -> Fut,
+    Fut: Future<Output = Result<()>>,
+{
+    let give_up = matches!(max_attempts, Some(max) if message.attempts() >= max);
+    if !give_up {
+        return match message.retry_with_options(retry.clone()) {
+            Ok(()) => Outcome::Retried(error, None),
+            // the message would be acked implicitly if it weren't marked for retry at all
+            Err(e) => {
+                message.retry();
+                Outcome::Retried(error, Some(e))
+            }
+        };
+    }
+
+    match dead_letter {
+        None => {
+            message.ack();
+            Outcome::Discarded(error)
+        }
+        Some(dead_letter) => match dead_letter().await {
+            Ok(()) => {
+                message.ack();
+                Outcome::DeadLettered(error)
+            }
+            // retry rather than lose the message, it'll be forwarded on its next attempt
+            Err(e) => {
+                message.retry();
+                Outcome::Retried(error, Some(e))
+            }
+        },
+    }
+}
+
+enum Outcome {
+    Succeeded,
+    /// The message will be delivered again, with the error of retrying or forwarding it if that
+    /// failed too.
+    Retried(Error, Option<Error>),
+    DeadLettered(Error),
+    Discarded(Error),
+}
+
+/// What happened to the messages of a batch passed through [`MessageBatch::process`].
+#[derive(Debug, Default)]
+pub struct ProcessSummary {
+    /// Messages whose handler succeeded, and which were acked.
+    pub succeeded: usize,
+    /// Messages which failed and will be delivered again.
+    pub retried: usize,
+    /// Messages which were given up on and forwarded to the dead-letter Queue.
+    pub dead_lettered: usize,
+    /// Messages which were given up on without a dead-letter Queue to forward them to.
+    pub discarded: usize,
+    /// Every message which didn't succeed, with the reason why.
+    pub failures: Vec<FailedMessage>,
+}
+
+impl ProcessSummary {
+    /// Whether every message succeeded.
+    pub fn is_success(&self) -> bool {
+        self.failures.is_empty()
+    }
+
+    fn record(&mut self, message: RawMessage, outcome: Outcome) {
+        let (error, settle_error) = match outcome {
+            Outcome::Succeeded => {
+                self.succeeded += 1;
+                return;
+            }
+            Outcome::Retried(error, settle_error) => {
+                self.retried += 1;
+                (error, settle_error)
+            }
+            Outcome::DeadLettered(error) => {
+                self.dead_lettered += 1;
+                (error, None)
+            }
+            Outcome::Discarded(error) => {
+                self.discarded += 1;
+                (error, None)
+            }
+        };
+
+        self.failures.push(FailedMessage {
+            id: message.id,
+            attempts: message.attempts,
+            error,
+            settle_error,
+        });
+    }
+}
+
+/// A message which failed while being processed by [`MessageBatch::process`].
+#[derive(Debug)]
+pub struct FailedMessage {
+    pub id: String,
+    /// The number of times delivery of this message had been attempted, including this one.
+    pub attempts: u32,
+    /// The error of the handler.
+    pub error: Error,
+    /// The error of retrying the message with the configured options, or of forwarding it to the
+    /// dead-letter Queue, if that failed as well. The message is retried without options instead.
+    pub settle_error: Option<Error>,
 }
 
 pub struct MessageIter<'a, T> {
     range: std::ops::Range<u32>,
     array: &'a Array,
-    timestamp_key: &'a JsValue,
-    body_key: &'a JsValue,
-    id_key: &'a JsValue,
     data: PhantomData<T>,
 }
 
@@ -90,20 +481,8 @@ impl<T> MessageIter<'_, T>
 where
     T: DeserializeOwned,
 {
-    fn parse_message(&self, message: &JsValue) -> Result<Message<T>> {
-        let js_date = js_sys::Date::from(js_sys::Reflect::get(message, self.timestamp_key)?);
-        let id = js_sys::Reflect::get(message, self.id_key)?
-            .as_string()
-            .ok_or(Error::JsError(
-                "Invalid message batch. Failed to get id from message.".to_string(),
-            ))?;
-        let body = serde_wasm_bindgen::from_value(js_sys::Reflect::get(message, self.body_key)?)?;
-
-        Ok(Message {
-            id,
-            body,
-            timestamp: Date::from(js_date),
-        })
+    fn parse_message(&self, message: JsValue) -> Result<Message<T>> {
+        RawMessage::new(message.unchecked_into()).into_message()
     }
 }
 
@@ -118,7 +497,7 @@ where
 
         let value = self.array.get(index);
 
-        Some(self.parse_message(&value))
+        Some(self.parse_message(value))
     }
 
     #[inline]
@@ -135,7 +514,7 @@ where
         let index = self.range.next_back()?;
         let value = self.array.get(index);
 
-        Some(self.parse_message(&value))
+        Some(self.parse_message(value))
     }
 }
 
@@ -187,4 +566,289 @@ impl Queue {
         fut.await.map_err(Error::from)?;
         Ok(())
     }
+
+    /// Sends a body which is already a JS value as it is, e.g. one delivered to a consumer.
+    async fn send_raw(&self, body: JsValue) -> Result<()> {
+        let fut: JsFuture = self.0.send(body).into();
+
+        fut.await.map_err(Error::from)?;
+        Ok(())
+    }
+
+    /// Sends a message to the Queue, e.g. with a content type other than the default `v8` or only
+    /// after a delay.
+    pub async fn send_with_options<T>(&self, message: &T, options: QueueSendOptions) -> Result<()>
+    where
+        T: Serialize,
+    {
+        let body = to_body(message, options.content_type)?;
+        let options = serde_wasm_bindgen::to_value(&options)?;
+        let fut: JsFuture = self.0.send_with_options(body, options).into();
+
+        fut.await.map_err(Error::from)?;
+        Ok(())
+    }
+
+    /// Sends the messages to the Queue, split into as many batches as the platform's limits on the
+    /// number and size of messages in a batch require. Messages can be given as their bodies, or
+    /// as [`SendMessage`]s to set options for each of them.
+    ///
+    /// Every batch is attempted even if an earlier one fails; the batches which failed are
+    /// returned, while an error is only returned if a message couldn't be serialized, in which
+    /// case nothing was sent.
+    pub async fn send_batch<T, I>(&self, messages: I) -> Result<Vec<FailedBatch>>
+    where
+        T: Serialize,
+        I: IntoIterator,
+        I::Item: Into<SendMessage<T>>,
+    {
+        let mut requests = vec![];
+        let mut sizes = vec![];
+        for message in messages {
+            let SendMessage { body, options } = message.into();
+            // the size can only be estimated, so bodies which can't be measured get a batch of
+            // their own
+            sizes.push(
+                serde_json::to_vec(&body)
+                    .map(|json| json.len())
+                    .unwrap_or(MAX_BATCH_BYTES),
+            );
+
+            let request = serde_wasm_bindgen::to_value(&options)?;
+            let body = to_body(&body, options.content_type)?;
+            js_sys::Reflect::set(&request, &"body".into(), &body)?;
+            requests.push(request);
+        }
+
+        let mut failed = vec![];
+        for range in batches(&sizes, MAX_BATCH_MESSAGES, MAX_BATCH_BYTES) {
+            let batch: Array = requests[range.clone()].iter().collect();
+            let fut: JsFuture = self.0.send_batch(batch).into();
+            if let Err(e) = fut.await {
+                failed.push(FailedBatch {
+                    messages: range,
+                    error: e.into(),
+                });
+            }
+        }
+
+        Ok(failed)
+    }
+}
+
+/// The most messages the platform accepts in a single batch.
+const MAX_BATCH_MESSAGES: usize = 100;
+/// The largest total size of the messages the platform accepts in a single batch.
+const MAX_BATCH_BYTES: usize = 256 * 1024;
+
+/// How the body of a message is encoded in the Queue.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum QueueContentType {
+    /// JSON, which consumers written in any language can read.
+    Json,
+    /// A string.
+    Text,
+    /// Raw bytes, e.g. from a `Vec<u8>`.
+    Bytes,
+    /// The structured clone format used by JavaScript, and the default.
+    V8,
+}
+
+/// Options for sending messages with [`Queue::send_with_options`] or in a [`SendMessage`].
+#[derive(Debug, Clone, Default, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct QueueSendOptions {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub content_type: Option<QueueContentType>,
+    /// How long to wait before the message is delivered.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub delay_seconds: Option<u32>,
+}
+
+/// A message to send with [`Queue::send_batch`], along with its options.
+#[derive(Debug, Clone)]
+pub struct SendMessage<T> {
+    pub body: T,
+    pub options: QueueSendOptions,
+}
+
+impl<T> SendMessage<T> {
+    pub fn new(body: T, options: QueueSendOptions) -> Self {
+        Self { body, options }
+    }
+}
+
+impl<T> From<T> for SendMessage<T> {
+    fn from(body: T) -> Self {
+        Self::new(body, QueueSendOptions::default())
+    }
+}
+
+/// A batch of messages which [`Queue::send_batch`] failed to send.
+#[derive(Debug)]
+pub struct FailedBatch {
+    /// The positions of the batch's messages among those given to `send_batch`.
+    pub messages: Range<usize>,
+    pub error: Error,
+}
+
+fn to_body<T: Serialize>(body: &T, content_type: Option<QueueContentType>) -> Result<JsValue> {
+    match content_type {
+        // JSON can't represent the maps `to_value` creates, so they become plain objects
+        Some(QueueContentType::Json) => Ok(body.serialize(&Serializer::json_compatible())?),
+        Some(QueueContentType::Bytes) => {
+            let body = serde_wasm_bindgen::to_value(body)?;
+            if Array::is_array(&body) {
+                Ok(Uint8Array::new(&body).into())
+            } else {
+                Ok(body)
+            }
+        }
+        _ => Ok(serde_wasm_bindgen::to_value(body)?),
+    }
+}
+
+/// Splits messages of the given sizes into consecutive batches within the limits. A message larger
+/// than `max_bytes` gets a batch of its own.
+fn batches(sizes: &[usize], max_messages: usize, max_bytes: usize) -> Vec<Range<usize>> {
+    let mut batches = vec![];
+    let mut start = 0;
+    let mut bytes = 0;
+
+    for (i, &size) in sizes.iter().enumerate() {
+        if i > start && (i - start == max_messages || bytes + size > max_bytes) {
+            batches.push(start..i);
+            start = i;
+            bytes = 0;
+        }
+        bytes += size;
+    }
+    if start < sizes.len() {
+        batches.push(start..sizes.len());
+    }
+
+    batches
+}
+
+#[test]
+fn batches_respect_limits() {
+    assert_eq!(batches(&[], 2, 10), vec![]);
+    assert_eq!(batches(&[1, 1, 1, 1, 1], 2, 10), vec![0..2, 2..4, 4..5]);
+    assert_eq!(
+        batches(&[4, 4, 4, 20, 1], 10, 10),
+        vec![0..2, 2..3, 3..4, 4..5]
+    );
+}
+
+#[test]
+fn settles_failed_messages() {
+    use futures_util::{future::Ready, FutureExt};
+    use std::cell::{Cell, RefCell};
+
+    #[derive(Default)]
+    struct TestMessage {
+        attempts: u32,
+        fail_retry_with_options: bool,
+        calls: RefCell<Vec<&'static str>>,
+    }
+
+    impl Settle for TestMessage {
+        fn attempts(&self) -> u32 {
+            self.attempts
+        }
+
+        fn ack(&self) {
+            self.calls.borrow_mut().push("ack");
+        }
+
+        fn retry(&self) {
+            self.calls.borrow_mut().push("retry");
+        }
+
+        fn retry_with_options(&self, _: QueueRetryOptions) -> Result<()> {
+            self.calls.borrow_mut().push("retry_with_options");
+            if self.fail_retry_with_options {
+                return Err(Error::RustError("invalid options".into()));
+            }
+            Ok(())
+        }
+    }
+
+    fn settle_now<F, Fut>(message: &TestMessage, dead_letter: Option<F>) -> Outcome
+    where
+        F: FnOnce() -> Fut,
+        Fut: Future<Output = Result<()>>,
+    {
+        let error = Error::RustError("handler failed".into());
+        settle(
+            message,
+            error,
+            Some(3),
+            &QueueRetryOptions::default(),
+            dead_letter,
+        )
+        .now_or_never()
+        .unwrap()
+    }
+    let no_dead_letter = None::<fn() -> Ready<Result<()>>>;
+    let is_error =
+        |error: &Error, expected: &str| matches!(error, Error::RustError(msg) if msg == expected);
+
+    // falls back to retrying without options, keeping the handler's error
+    let message = TestMessage {
+        attempts: 1,
+        fail_retry_with_options: true,
+        ..TestMessage::default()
+    };
+    match settle_now(&message, no_dead_letter) {
+        Outcome::Retried(error, Some(settle_error)) => {
+            assert!(is_error(&error, "handler failed"));
+            assert!(is_error(&settle_error, "invalid options"));
+        }
+        _ => panic!("the message wasn't retried"),
+    }
+    assert_eq!(*message.calls.borrow(), ["retry_with_options", "retry"]);
+
+    // retries a message which couldn't be forwarded to the dead-letter Queue
+    let message = TestMessage {
+        attempts: 3,
+        ..TestMessage::default()
+    };
+    let forwarded = Cell::new(false);
+    let outcome = settle_now(
+        &message,
+        Some(|| {
+            forwarded.set(true);
+            async { Err(Error::RustError("queue unavailable".into())) }
+        }),
+    );
+    match outcome {
+        Outcome::Retried(error, Some(settle_error)) => {
+            assert!(is_error(&error, "handler failed"));
+            assert!(is_error(&settle_error, "queue unavailable"));
+        }
+        _ => panic!("the message wasn't retried"),
+    }
+    assert!(forwarded.get());
+    assert_eq!(*message.calls.borrow(), ["retry"]);
+
+    // gives up once the message has been attempted `max_attempts` times
+    let message = TestMessage {
+        attempts: 3,
+        ..TestMessage::default()
+    };
+    let outcome = settle_now(&message, Some(|| async { Ok(()) }));
+    assert!(matches!(outcome, Outcome::DeadLettered(_)));
+    assert_eq!(*message.calls.borrow(), ["ack"]);
+
+    let message = TestMessage {
+        attempts: 3,
+        ..TestMessage::default()
+    };
+    assert!(matches!(
+        settle_now(&message, no_dead_letter),
+        Outcome::Discarded(_)
+    ));
+    assert_eq!(*message.calls.borrow(), ["ack"]);
 }
@@ -227,6 +227,11 @@ impl Request {
         Ok(&mut self.path)
     }
 
+    // used by the `Router` to rewrite the path of requests forwarded to a mounted router
+    pub(crate) fn set_path(&mut self, path: String) {
+        self.path = path;
+    }
+
     /// The parsed [`url::Url`] of this `Request`.
     pub fn url(&self) -> Result<Url> {
         let url = self.edge_request.url();
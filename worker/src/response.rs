@@ -1,3 +1,5 @@
+use std::convert::TryFrom;
+
 use crate::cors::Cors;
 use crate::error::Error;
 use crate::headers::Headers;
@@ -322,6 +324,96 @@ impl Response {
     }
 }
 
+/// Types which can be returned from a route handler or the `#[event(fetch)]` handler, and are
+/// converted into a `Response` to send back to the client.
+///
+/// `Error` is converted by returning it as is, so a handler returning `Result<T>` still propagates
+/// its errors rather than turning them into a response.
+pub trait IntoResponse {
+    /// Convert `self` into a `Response`.
+    fn into_response(self) -> Result<Response>;
+}
+
+impl IntoResponse for Response {
+    fn into_response(self) -> Result<Response> {
+        Ok(self)
+    }
+}
+
+impl IntoResponse for Error {
+    fn into_response(self) -> Result<Response> {
+        Err(self)
+    }
+}
+
+/// Both variants are converted into a response, so a handler can return its own error type, e.g.
+/// `Result<Response, ProblemDetails>`, as long as that implements `IntoResponse`.
+impl<T: IntoResponse, E: IntoResponse> IntoResponse for std::result::Result<T, E> {
+    fn into_response(self) -> Result<Response> {
+        match self {
+            Ok(value) => value.into_response(),
+            Err(e) => e.into_response(),
+        }
+    }
+}
+
+impl IntoResponse for String {
+    fn into_response(self) -> Result<Response> {
+        Response::ok(self)
+    }
+}
+
+impl IntoResponse for &str {
+    fn into_response(self) -> Result<Response> {
+        Response::ok(self)
+    }
+}
+
+impl IntoResponse for Vec<u8> {
+    fn into_response(self) -> Result<Response> {
+        Response::from_bytes(self)
+    }
+}
+
+impl IntoResponse for serde_json::Value {
+    fn into_response(self) -> Result<Response> {
+        Response::from_json(&self)
+    }
+}
+
+/// Overrides the status code of the response.
+impl<T: IntoResponse> IntoResponse for (u16, T) {
+    fn into_response(self) -> Result<Response> {
+        let (status_code, value) = self;
+        Ok(value.into_response()?.with_status(status_code))
+    }
+}
+
+/// Adds the headers to the response, replacing any existing values of the same name.
+impl<T: IntoResponse> IntoResponse for (Headers, T) {
+    fn into_response(self) -> Result<Response> {
+        let (headers, value) = self;
+        let mut resp = value.into_response()?;
+        for name in headers.keys() {
+            resp.headers_mut().delete(&name)?;
+        }
+        for (name, value) in headers.entries() {
+            resp.headers_mut().append(&name, &value)?;
+        }
+        Ok(resp)
+    }
+}
+
+impl<B> IntoResponse for http::Response<B>
+where
+    B: http_body::Body + 'static,
+    B::Error: std::error::Error,
+{
+    fn into_response(self) -> Result<Response> {
+        Response::try_from(self)
+    }
+}
+
 #[test]
 fn no_using_invalid_error_status_code() {
     assert!(Response::error("OK", 200).is_err());
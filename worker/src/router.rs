@@ -1,4 +1,4 @@
-use std::{collections::HashMap, future::Future, rc::Rc};
+use std::{cell::RefCell, collections::HashMap, future::Future, rc::Rc};
 
 use futures_util::future::LocalBoxFuture;
 use matchit::Match;
@@ -10,42 +10,168 @@ use crate::{
     env::{Env, Secret, Var},
     method::Method,
     request::Request,
-    response::Response,
-    Bucket, Fetcher, Result,
+    response::{Response, ResponseBody},
+    Bucket, Cors, Error, Fetcher, IntoResponse, Result,
 };
 
-type HandlerFn<D> = fn(Request, RouteContext<D>) -> Result<Response>;
+/// Name of the catch-all parameter used to capture the remainder of a path under a mount prefix.
+const MOUNT_PATH_PARAM: &str = "__mount_path";
+
+type HandlerFn<'a, D> = Rc<dyn 'a + Fn(Request, RouteContext<D>) -> Result<Response>>;
 type AsyncHandlerFn<'a, D> =
     Rc<dyn 'a + Fn(Request, RouteContext<D>) -> LocalBoxFuture<'a, Result<Response>>>;
+type ErrorHandlerFn<'a> = Box<dyn 'a + Fn(Error) -> Result<Response>>;
+type MiddlewareFn<'a, D> =
+    Rc<dyn 'a + Fn(Request, RouteContext<D>, Next<'a, D>) -> LocalBoxFuture<'a, Result<Response>>>;
 
 /// Represents the URL parameters parsed from the path, e.g. a route with "/user/:id" pattern would
-/// contain a single "id" key.
-pub struct RouteParams(HashMap<String, String>);
+/// contain a single "id" key. Parameters are kept in the order they appear in the pattern.
+#[derive(Default)]
+pub struct RouteParams {
+    params: Vec<(String, String)>,
+    /// How many of the leading `params` were captured by the route's own pattern, the rest being
+    /// inherited from the mount prefixes of parent routers.
+    own: usize,
+}
 
 impl RouteParams {
     fn get(&self, key: &str) -> Option<&String> {
-        self.0.get(key)
+        self.params.iter().find(|(k, _)| k == key).map(|(_, v)| v)
+    }
+
+    fn remove(&mut self, key: &str) -> Option<String> {
+        let index = self.params.iter().position(|(k, _)| k == key)?;
+        if index < self.own {
+            self.own -= 1;
+        }
+        Some(self.params.remove(index).1)
+    }
+
+    /// Append the parameters captured by a parent router's mount prefix, unless shadowed by a
+    /// parameter of the same name. They can only be looked up by name, so that positional
+    /// extractors such as `Path<(A, B)>` don't depend on where a router is mounted.
+    fn inherit(&mut self, parent: RouteParams) {
+        let params: Vec<_> = parent
+            .params
+            .into_iter()
+            .filter(|(key, _)| self.get(key).is_none())
+            .collect();
+        self.params.extend(params);
+    }
+
+    /// Every parameter, the route's own followed by the inherited ones.
+    pub(crate) fn as_slice(&self) -> &[(String, String)] {
+        &self.params
+    }
+
+    /// The parameters captured by the route's own pattern.
+    pub(crate) fn own(&self) -> &[(String, String)] {
+        &self.params[..self.own]
     }
 }
 
 enum Handler<'a, D> {
     Async(AsyncHandlerFn<'a, D>),
-    Sync(HandlerFn<D>),
+    Sync(HandlerFn<'a, D>),
+}
+
+impl<'a, D: 'a> Handler<'a, D> {
+    fn from_sync<R: IntoResponse>(func: impl Fn(Request, RouteContext<D>) -> R + 'a) -> Self {
+        Self::Sync(Rc::new(move |req, ctx| func(req, ctx).into_response()))
+    }
+
+    fn from_async<T>(func: impl Fn(Request, RouteContext<D>) -> T + 'a) -> Self
+    where
+        T: Future + 'a,
+        T::Output: IntoResponse,
+    {
+        Self::Async(Rc::new(move |req, ctx| {
+            let fut = func(req, ctx);
+            Box::pin(async move { fut.await.into_response() })
+        }))
+    }
+}
+
+impl<'a, D: 'a> Handler<'a, D> {
+    async fn call(self, req: Request, ctx: RouteContext<D>) -> Result<Response> {
+        match self {
+            Handler::Sync(func) => (func)(req, ctx),
+            Handler::Async(func) => (func)(req, ctx).await,
+        }
+    }
+
+    /// Answer a HEAD request with the status and headers the GET handler responds with.
+    fn head_from_get(get: Self) -> Self {
+        Handler::Async(Rc::new(move |req, ctx| {
+            let get = get.clone();
+            Box::pin(async move {
+                let resp = get.call(req, ctx).await?;
+                Ok(Response::from_body(ResponseBody::Empty)?
+                    .with_headers(resp.headers().clone())
+                    .with_status(resp.status_code()))
+            })
+        }))
+    }
 }
 
 impl<D> Clone for Handler<'_, D> {
     fn clone(&self) -> Self {
         match self {
             Self::Async(rc) => Self::Async(rc.clone()),
-            Self::Sync(func) => Self::Sync(*func),
+            Self::Sync(rc) => Self::Sync(rc.clone()),
+        }
+    }
+}
+
+struct Middleware<'a, D> {
+    scope: Option<Node<()>>,
+    func: MiddlewareFn<'a, D>,
+}
+
+impl<D> Middleware<'_, D> {
+    fn applies_to(&self, path: &str) -> bool {
+        match &self.scope {
+            Some(scope) => scope.at(path).is_ok(),
+            None => true,
+        }
+    }
+}
+
+/// The remainder of a middleware chain, ending with the handler matched by the `Router`.
+///
+/// Middleware receives a `Next` alongside the `Request` and may call [`Next::run`] to continue
+/// processing, or return a `Response` of its own to short-circuit the chain.
+pub struct Next<'a, D> {
+    middleware: std::vec::IntoIter<MiddlewareFn<'a, D>>,
+    handler: Handler<'a, D>,
+}
+
+impl<'a, D: 'a> Next<'a, D> {
+    /// Pass the request on to the next middleware, or to the route handler once every middleware
+    /// has run, and return the resulting `Response`.
+    pub async fn run(mut self, req: Request, ctx: RouteContext<D>) -> Result<Response> {
+        match self.middleware.next() {
+            Some(func) => (func)(req, ctx, self).await,
+            None => self.handler.call(req, ctx).await,
         }
     }
 }
 
 /// A path-based HTTP router supporting exact-match or wildcard placeholders and shared data.
+///
+/// Handlers can be plain functions or closures, so state known when building the `Router` (for
+/// example configuration read from the `Env`) can be captured directly by the routes that need it.
+///
+/// Unless a handler is registered for them, HEAD requests are answered by the GET handler with its
+/// body removed, and OPTIONS requests with the `Allow`ed methods for the path. The `Allow` header
+/// is also included in "Method Not Allowed" responses.
 pub struct Router<'a, D> {
     handlers: HashMap<Method, Node<Handler<'a, D>>>,
     or_else_any_method: Node<Handler<'a, D>>,
+    mounts: Node<Handler<'a, D>>,
+    middleware: Vec<Middleware<'a, D>>,
+    error_handler: Option<ErrorHandlerFn<'a>>,
+    cors: Option<Cors>,
     data: D,
 }
 
@@ -96,6 +222,10 @@ impl<D> RouteContext<D> {
         self.params.get(key)
     }
 
+    pub(crate) fn params(&self) -> &RouteParams {
+        &self.params
+    }
+
     /// Get a [Service Binding](https://developers.cloudflare.com/workers/runtime-apis/service-bindings/)
     /// for Worker-to-Worker communication.
     pub fn service(&self, binding: &str) -> Result<Fetcher> {
@@ -122,148 +252,194 @@ impl<'a, D: 'a> Router<'a, D> {
         Self {
             handlers: HashMap::new(),
             or_else_any_method: Node::new(),
+            mounts: Node::new(),
+            middleware: Vec::new(),
+            error_handler: None,
+            cors: None,
             data,
         }
     }
 
     /// Register an HTTP handler that will exclusively respond to HEAD requests.
-    pub fn head(mut self, pattern: &str, func: HandlerFn<D>) -> Self {
-        self.add_handler(pattern, Handler::Sync(func), vec![Method::Head]);
+    pub fn head<R: IntoResponse>(
+        mut self,
+        pattern: &str,
+        func: impl Fn(Request, RouteContext<D>) -> R + 'a,
+    ) -> Self {
+        self.add_handler(pattern, Handler::from_sync(func), vec![Method::Head]);
         self
     }
 
     /// Register an HTTP handler that will exclusively respond to GET requests.
-    pub fn get(mut self, pattern: &str, func: HandlerFn<D>) -> Self {
-        self.add_handler(pattern, Handler::Sync(func), vec![Method::Get]);
+    pub fn get<R: IntoResponse>(
+        mut self,
+        pattern: &str,
+        func: impl Fn(Request, RouteContext<D>) -> R + 'a,
+    ) -> Self {
+        self.add_handler(pattern, Handler::from_sync(func), vec![Method::Get]);
         self
     }
 
     /// Register an HTTP handler that will exclusively respond to POST requests.
-    pub fn post(mut self, pattern: &str, func: HandlerFn<D>) -> Self {
-        self.add_handler(pattern, Handler::Sync(func), vec![Method::Post]);
+    pub fn post<R: IntoResponse>(
+        mut self,
+        pattern: &str,
+        func: impl Fn(Request, RouteContext<D>) -> R + 'a,
+    ) -> Self {
+        self.add_handler(pattern, Handler::from_sync(func), vec![Method::Post]);
         self
     }
 
     /// Register an HTTP handler that will exclusively respond to PUT requests.
-    pub fn put(mut self, pattern: &str, func: HandlerFn<D>) -> Self {
-        self.add_handler(pattern, Handler::Sync(func), vec![Method::Put]);
+    pub fn put<R: IntoResponse>(
+        mut self,
+        pattern: &str,
+        func: impl Fn(Request, RouteContext<D>) -> R + 'a,
+    ) -> Self {
+        self.add_handler(pattern, Handler::from_sync(func), vec![Method::Put]);
         self
     }
 
     /// Register an HTTP handler that will exclusively respond to PATCH requests.
-    pub fn patch(mut self, pattern: &str, func: HandlerFn<D>) -> Self {
-        self.add_handler(pattern, Handler::Sync(func), vec![Method::Patch]);
+    pub fn patch<R: IntoResponse>(
+        mut self,
+        pattern: &str,
+        func: impl Fn(Request, RouteContext<D>) -> R + 'a,
+    ) -> Self {
+        self.add_handler(pattern, Handler::from_sync(func), vec![Method::Patch]);
         self
     }
 
     /// Register an HTTP handler that will exclusively respond to DELETE requests.
-    pub fn delete(mut self, pattern: &str, func: HandlerFn<D>) -> Self {
-        self.add_handler(pattern, Handler::Sync(func), vec![Method::Delete]);
+    pub fn delete<R: IntoResponse>(
+        mut self,
+        pattern: &str,
+        func: impl Fn(Request, RouteContext<D>) -> R + 'a,
+    ) -> Self {
+        self.add_handler(pattern, Handler::from_sync(func), vec![Method::Delete]);
         self
     }
 
     /// Register an HTTP handler that will exclusively respond to OPTIONS requests.
-    pub fn options(mut self, pattern: &str, func: HandlerFn<D>) -> Self {
-        self.add_handler(pattern, Handler::Sync(func), vec![Method::Options]);
+    pub fn options<R: IntoResponse>(
+        mut self,
+        pattern: &str,
+        func: impl Fn(Request, RouteContext<D>) -> R + 'a,
+    ) -> Self {
+        self.add_handler(pattern, Handler::from_sync(func), vec![Method::Options]);
         self
     }
 
     /// Register an HTTP handler that will respond to any requests.
-    pub fn on(mut self, pattern: &str, func: HandlerFn<D>) -> Self {
-        self.add_handler(pattern, Handler::Sync(func), Method::all());
+    pub fn on<R: IntoResponse>(
+        mut self,
+        pattern: &str,
+        func: impl Fn(Request, RouteContext<D>) -> R + 'a,
+    ) -> Self {
+        self.add_handler(pattern, Handler::from_sync(func), Method::all());
         self
     }
 
     /// Register an HTTP handler that will respond to all methods that are not handled explicitly by
     /// other handlers.
-    pub fn or_else_any_method(mut self, pattern: &str, func: HandlerFn<D>) -> Self {
+    pub fn or_else_any_method<R: IntoResponse>(
+        mut self,
+        pattern: &str,
+        func: impl Fn(Request, RouteContext<D>) -> R + 'a,
+    ) -> Self {
         self.or_else_any_method
-            .insert(pattern, Handler::Sync(func))
+            .insert(pattern, Handler::from_sync(func))
             .unwrap_or_else(|e| panic!("failed to register route for {} pattern: {}", pattern, e));
         self
     }
 
     /// Register an HTTP handler that will exclusively respond to HEAD requests. Enables the use of
     /// `async/await` syntax in the callback.
-    pub fn head_async<T>(mut self, pattern: &str, func: fn(Request, RouteContext<D>) -> T) -> Self
+    pub fn head_async<T>(
+        mut self,
+        pattern: &str,
+        func: impl Fn(Request, RouteContext<D>) -> T + 'a,
+    ) -> Self
     where
-        T: Future<Output = Result<Response>> + 'a,
+        T: Future + 'a,
+        T::Output: IntoResponse,
     {
-        self.add_handler(
-            pattern,
-            Handler::Async(Rc::new(move |req, info| Box::pin(func(req, info)))),
-            vec![Method::Head],
-        );
+        self.add_handler(pattern, Handler::from_async(func), vec![Method::Head]);
         self
     }
 
     /// Register an HTTP handler that will exclusively respond to GET requests. Enables the use of
     /// `async/await` syntax in the callback.
-    pub fn get_async<T>(mut self, pattern: &str, func: fn(Request, RouteContext<D>) -> T) -> Self
+    pub fn get_async<T>(
+        mut self,
+        pattern: &str,
+        func: impl Fn(Request, RouteContext<D>) -> T + 'a,
+    ) -> Self
     where
-        T: Future<Output = Result<Response>> + 'a,
+        T: Future + 'a,
+        T::Output: IntoResponse,
     {
-        self.add_handler(
-            pattern,
-            Handler::Async(Rc::new(move |req, info| Box::pin(func(req, info)))),
-            vec![Method::Get],
-        );
+        self.add_handler(pattern, Handler::from_async(func), vec![Method::Get]);
         self
     }
 
     /// Register an HTTP handler that will exclusively respond to POST requests. Enables the use of
     /// `async/await` syntax in the callback.
-    pub fn post_async<T>(mut self, pattern: &str, func: fn(Request, RouteContext<D>) -> T) -> Self
+    pub fn post_async<T>(
+        mut self,
+        pattern: &str,
+        func: impl Fn(Request, RouteContext<D>) -> T + 'a,
+    ) -> Self
     where
-        T: Future<Output = Result<Response>> + 'a,
+        T: Future + 'a,
+        T::Output: IntoResponse,
     {
-        self.add_handler(
-            pattern,
-            Handler::Async(Rc::new(move |req, info| Box::pin(func(req, info)))),
-            vec![Method::Post],
-        );
+        self.add_handler(pattern, Handler::from_async(func), vec![Method::Post]);
         self
     }
 
     /// Register an HTTP handler that will exclusively respond to PUT requests. Enables the use of
     /// `async/await` syntax in the callback.
-    pub fn put_async<T>(mut self, pattern: &str, func: fn(Request, RouteContext<D>) -> T) -> Self
+    pub fn put_async<T>(
+        mut self,
+        pattern: &str,
+        func: impl Fn(Request, RouteContext<D>) -> T + 'a,
+    ) -> Self
     where
-        T: Future<Output = Result<Response>> + 'a,
+        T: Future + 'a,
+        T::Output: IntoResponse,
     {
-        self.add_handler(
-            pattern,
-            Handler::Async(Rc::new(move |req, info| Box::pin(func(req, info)))),
-            vec![Method::Put],
-        );
+        self.add_handler(pattern, Handler::from_async(func), vec![Method::Put]);
         self
     }
 
     /// Register an HTTP handler that will exclusively respond to PATCH requests. Enables the use of
     /// `async/await` syntax in the callback.
-    pub fn patch_async<T>(mut self, pattern: &str, func: fn(Request, RouteContext<D>) -> T) -> Self
+    pub fn patch_async<T>(
+        mut self,
+        pattern: &str,
+        func: impl Fn(Request, RouteContext<D>) -> T + 'a,
+    ) -> Self
     where
-        T: Future<Output = Result<Response>> + 'a,
+        T: Future + 'a,
+        T::Output: IntoResponse,
     {
-        self.add_handler(
-            pattern,
-            Handler::Async(Rc::new(move |req, info| Box::pin(func(req, info)))),
-            vec![Method::Patch],
-        );
+        self.add_handler(pattern, Handler::from_async(func), vec![Method::Patch]);
         self
     }
 
     /// Register an HTTP handler that will exclusively respond to DELETE requests. Enables the use
     /// of `async/await` syntax in the callback.
-    pub fn delete_async<T>(mut self, pattern: &str, func: fn(Request, RouteContext<D>) -> T) -> Self
+    pub fn delete_async<T>(
+        mut self,
+        pattern: &str,
+        func: impl Fn(Request, RouteContext<D>) -> T + 'a,
+    ) -> Self
     where
-        T: Future<Output = Result<Response>> + 'a,
+        T: Future + 'a,
+        T::Output: IntoResponse,
     {
-        self.add_handler(
-            pattern,
-            Handler::Async(Rc::new(move |req, info| Box::pin(func(req, info)))),
-            vec![Method::Delete],
-        );
+        self.add_handler(pattern, Handler::from_async(func), vec![Method::Delete]);
         self
     }
 
@@ -272,30 +448,28 @@ impl<'a, D: 'a> Router<'a, D> {
     pub fn options_async<T>(
         mut self,
         pattern: &str,
-        func: fn(Request, RouteContext<D>) -> T,
+        func: impl Fn(Request, RouteContext<D>) -> T + 'a,
     ) -> Self
     where
-        T: Future<Output = Result<Response>> + 'a,
+        T: Future + 'a,
+        T::Output: IntoResponse,
     {
-        self.add_handler(
-            pattern,
-            Handler::Async(Rc::new(move |req, info| Box::pin(func(req, info)))),
-            vec![Method::Options],
-        );
+        self.add_handler(pattern, Handler::from_async(func), vec![Method::Options]);
         self
     }
 
     /// Register an HTTP handler that will respond to any requests. Enables the use of `async/await`
     /// syntax in the callback.
-    pub fn on_async<T>(mut self, pattern: &str, func: fn(Request, RouteContext<D>) -> T) -> Self
+    pub fn on_async<T>(
+        mut self,
+        pattern: &str,
+        func: impl Fn(Request, RouteContext<D>) -> T + 'a,
+    ) -> Self
     where
-        T: Future<Output = Result<Response>> + 'a,
+        T: Future + 'a,
+        T::Output: IntoResponse,
     {
-        self.add_handler(
-            pattern,
-            Handler::Async(Rc::new(move |req, route| Box::pin(func(req, route)))),
-            Method::all(),
-        );
+        self.add_handler(pattern, Handler::from_async(func), Method::all());
         self
     }
 
@@ -304,20 +478,144 @@ impl<'a, D: 'a> Router<'a, D> {
     pub fn or_else_any_method_async<T>(
         mut self,
         pattern: &str,
-        func: fn(Request, RouteContext<D>) -> T,
+        func: impl Fn(Request, RouteContext<D>) -> T + 'a,
     ) -> Self
     where
-        T: Future<Output = Result<Response>> + 'a,
+        T: Future + 'a,
+        T::Output: IntoResponse,
     {
         self.or_else_any_method
-            .insert(
-                pattern,
-                Handler::Async(Rc::new(move |req, route| Box::pin(func(req, route)))),
-            )
+            .insert(pattern, Handler::from_async(func))
             .unwrap_or_else(|e| panic!("failed to register route for {} pattern: {}", pattern, e));
         self
     }
 
+    /// Register middleware that wraps every request handled by this `Router`, including requests
+    /// which end up with a "Not Found" or "Method Not Allowed" response. Middleware runs in the
+    /// order it was registered, so the first middleware registered is the outermost.
+    ///
+    /// ```no_run
+    /// # use worker::*;
+    /// # fn example<'a>(router: Router<'a, ()>) -> Router<'a, ()> {
+    /// router.middleware(|req, ctx, next| async move {
+    ///     if req.headers().get("authorization")?.is_none() {
+    ///         return Response::error("Unauthorized", 401);
+    ///     }
+    ///
+    ///     let mut res = next.run(req, ctx).await?;
+    ///     res.headers_mut().set("x-powered-by", "workers-rs")?;
+    ///     Ok(res)
+    /// })
+    /// # }
+    /// ```
+    pub fn middleware<T>(
+        mut self,
+        func: impl Fn(Request, RouteContext<D>, Next<'a, D>) -> T + 'a,
+    ) -> Self
+    where
+        T: Future<Output = Result<Response>> + 'a,
+    {
+        self.middleware.push(Middleware {
+            scope: None,
+            func: Rc::new(move |req, ctx, next| Box::pin(func(req, ctx, next))),
+        });
+        self
+    }
+
+    /// Register middleware that only wraps requests whose path matches the provided pattern, e.g.
+    /// `/admin/*path` to guard a group of routes. Patterns use the same syntax as route handlers.
+    pub fn middleware_for<T>(
+        mut self,
+        pattern: &str,
+        func: impl Fn(Request, RouteContext<D>, Next<'a, D>) -> T + 'a,
+    ) -> Self
+    where
+        T: Future<Output = Result<Response>> + 'a,
+    {
+        let mut scope = Node::new();
+        scope.insert(pattern, ()).unwrap_or_else(|e| {
+            panic!(
+                "failed to register middleware for {} pattern: {}",
+                pattern, e
+            )
+        });
+        self.middleware.push(Middleware {
+            scope: Some(scope),
+            func: Rc::new(move |req, ctx, next| Box::pin(func(req, ctx, next))),
+        });
+        self
+    }
+
+    /// Register a handler which converts any `Error` returned by a route handler or middleware into
+    /// a response, e.g. with a status code and a JSON [`ProblemDetails`](crate::ProblemDetails)
+    /// body. Errors returned by the error handler itself are returned from `Router::run`.
+    ///
+    /// ```no_run
+    /// # use worker::*;
+    /// # fn example<'a>(router: Router<'a, ()>) -> Router<'a, ()> {
+    /// router.on_error(|e| match e.downcast_ref::<std::num::ParseIntError>() {
+    ///     Some(_) => ProblemDetails::new(400).with_detail("expected a number"),
+    ///     None => ProblemDetails::from(e),
+    /// })
+    /// # }
+    /// ```
+    pub fn on_error<R: IntoResponse>(mut self, func: impl Fn(Error) -> R + 'a) -> Self {
+        self.error_handler = Some(Box::new(move |e| func(e).into_response()));
+        self
+    }
+
+    /// Enforce the `Cors` policy for every request handled by this `Router`: CORS preflight requests
+    /// are answered by [`Cors::preflight`] without reaching any handler, and all other responses
+    /// are decorated by [`Cors::apply_to`], including those returned by middleware. Errors are
+    /// only decorated once converted into responses by the handler registered with
+    /// [`on_error`](Self::on_error), and are returned as they are otherwise.
+    pub fn cors(mut self, cors: Cors) -> Self {
+        self.cors = Some(cors);
+        self
+    }
+
+    /// Mount another `Router` under a path prefix, such as `/api/v1/users` or `/orgs/:org`. Any
+    /// request whose path starts with the prefix is forwarded to the mounted router, which can
+    /// have its own data type and middleware.
+    ///
+    /// The mounted router matches its routes against the remainder of the path, which is also
+    /// what `Request::path` returns within its handlers (`Request::url` is left untouched). URL
+    /// parameters captured by the prefix can be read by name, through `RouteContext::param` or a
+    /// `Path` struct, while positional extractors such as `Path<(A, B)>` only see the mounted
+    /// route's own parameters.
+    /// Routes registered directly on this `Router` take precedence over mounted routers.
+    pub fn mount<T: 'a>(mut self, prefix: &str, router: Router<'a, T>) -> Self {
+        let prefix = prefix.trim_end_matches('/');
+        let router = Rc::new(RefCell::new(Some(router)));
+        let handler: Handler<'a, D> = Handler::Async(Rc::new(move |mut req, ctx| {
+            let router = router.borrow_mut().take();
+            Box::pin(async move {
+                let router = router.ok_or_else(|| {
+                    Error::RustError("mounted router has already handled a request".into())
+                })?;
+
+                let RouteContext {
+                    env, mut params, ..
+                } = ctx;
+                let path = params.remove(MOUNT_PATH_PARAM).unwrap_or_default();
+                req.set_path(format!("/{path}"));
+
+                router.handle(req, env, params).await
+            })
+        }));
+
+        let patterns = [
+            if prefix.is_empty() { "/" } else { prefix }.to_string(),
+            format!("{prefix}/*{MOUNT_PATH_PARAM}"),
+        ];
+        for pattern in patterns.iter() {
+            self.mounts
+                .insert(pattern, handler.clone())
+                .unwrap_or_else(|e| panic!("failed to mount router at {} pattern: {}", pattern, e));
+        }
+        self
+    }
+
     fn add_handler(&mut self, pattern: &str, func: Handler<'a, D>, methods: Vec<Method>) {
         for method in methods {
             self.handlers
@@ -335,70 +633,227 @@ impl<'a, D: 'a> Router<'a, D> {
 
     /// Handle the request provided to the `Router` and return a `Future`.
     pub async fn run(self, req: Request, env: Env) -> Result<Response> {
-        let (handlers, data, or_else_any_method_handler) = self.split();
-
-        if let Some(handlers) = handlers.get(&req.method()) {
-            if let Ok(Match { value, params }) = handlers.at(&req.path()) {
-                let route_info = RouteContext {
-                    data,
-                    env,
-                    params: params.into(),
-                };
-                return match value {
-                    Handler::Sync(func) => (func)(req, route_info),
-                    Handler::Async(func) => (func)(req, route_info).await,
-                };
+        self.handle(req, env, RouteParams::default()).await
+    }
+
+    async fn handle(self, req: Request, env: Env, inherited: RouteParams) -> Result<Response> {
+        let origin = match &self.cors {
+            Some(cors) if Cors::is_preflight(&req)? => return cors.preflight(&req),
+            Some(_) => req.headers().get("Origin")?,
+            None => None,
+        };
+
+        let path = req.path();
+        let (handler, mut params) = self.resolve(&req.method(), &path);
+        params.inherit(inherited);
+
+        let middleware: Vec<_> = self
+            .middleware
+            .iter()
+            .filter(|middleware| middleware.applies_to(&path))
+            .map(|middleware| middleware.func.clone())
+            .collect();
+
+        let route_info = RouteContext {
+            data: self.data,
+            env,
+            params,
+        };
+
+        let result = Next {
+            middleware: middleware.into_iter(),
+            handler,
+        }
+        .run(req, route_info)
+        .await;
+
+        let result = match (result, &self.error_handler) {
+            (Err(e), Some(error_handler)) => error_handler(e),
+            (result, _) => result,
+        };
+
+        match (result, &self.cors) {
+            (Ok(mut resp), Some(cors)) => {
+                cors.apply_to_origin(origin.as_deref(), resp.headers_mut())?;
+                Ok(resp)
             }
+            (result, _) => result,
         }
+    }
 
-        for method in Method::all() {
-            if method == Method::Head || method == Method::Options || method == Method::Trace {
-                continue;
+    fn resolve(&self, method: &Method, path: &str) -> (Handler<'a, D>, RouteParams) {
+        if let Some(handlers) = self.handlers.get(method) {
+            if let Ok(Match { value, params }) = handlers.at(path) {
+                return (value.clone(), params.into());
             }
-            if let Some(handlers) = handlers.get(&method) {
-                if let Ok(Match { .. }) = handlers.at(&req.path()) {
-                    return Response::error("Method Not Allowed", 405);
+        }
+
+        if *method == Method::Head {
+            if let Some(handlers) = self.handlers.get(&Method::Get) {
+                if let Ok(Match { value, params }) = handlers.at(path) {
+                    return (Handler::head_from_get(value.clone()), params.into());
                 }
             }
         }
 
-        if let Ok(Match { value, params }) = or_else_any_method_handler.at(&req.path()) {
-            let route_info = RouteContext {
-                data,
-                env,
-                params: params.into(),
-            };
-            return match value {
-                Handler::Sync(func) => (func)(req, route_info),
-                Handler::Async(func) => (func)(req, route_info).await,
-            };
+        if let Ok(Match { value, params }) = self.mounts.at(path) {
+            return (value.clone(), params.into());
+        }
+
+        let allowed = self.allowed_methods(path);
+        if *method == Method::Options && !allowed.is_empty() {
+            let allow = allow_header(&allowed);
+            return (
+                Handler::Sync(Rc::new(move |_, _| {
+                    let mut resp = Response::empty()?.with_status(204);
+                    resp.headers_mut().set("Allow", &allow)?;
+                    Ok(resp)
+                })),
+                RouteParams::default(),
+            );
+        }
+
+        let not_allowed = [Method::Head, Method::Options, Method::Trace];
+        if allowed.iter().any(|method| !not_allowed.contains(method)) {
+            let allow = allow_header(&allowed);
+            return (
+                Handler::Sync(Rc::new(move |_, _| {
+                    let mut resp = Response::error("Method Not Allowed", 405)?;
+                    resp.headers_mut().set("Allow", &allow)?;
+                    Ok(resp)
+                })),
+                RouteParams::default(),
+            );
+        }
+
+        if let Ok(Match { value, params }) = self.or_else_any_method.at(path) {
+            return (value.clone(), params.into());
         }
 
-        Response::error("Not Found", 404)
+        (
+            Handler::Sync(Rc::new(|_, _| Response::error("Not Found", 404))),
+            RouteParams::default(),
+        )
     }
-}
 
-type NodeWithHandlers<'a, D> = Node<Handler<'a, D>>;
+    /// The methods with a handler registered for the path, in the order of `Method::all`.
+    fn allowed_methods(&self, path: &str) -> Vec<Method> {
+        Method::all()
+            .into_iter()
+            .filter(|method| match self.handlers.get(method) {
+                Some(handlers) => handlers.at(path).is_ok(),
+                None => false,
+            })
+            .collect()
+    }
+}
 
-impl<'a, D: 'a> Router<'a, D> {
-    fn split(
-        self,
-    ) -> (
-        HashMap<Method, NodeWithHandlers<'a, D>>,
-        D,
-        NodeWithHandlers<'a, D>,
-    ) {
-        (self.handlers, self.data, self.or_else_any_method)
+/// The value of the `Allow` header for a path with handlers for the given methods, which also
+/// includes the HEAD and OPTIONS requests the `Router` answers on its own.
+fn allow_header(methods: &[Method]) -> String {
+    let mut allow: Vec<&str> = methods.iter().map(|method| method.as_ref()).collect();
+    if methods.contains(&Method::Get) && !methods.contains(&Method::Head) {
+        allow.insert(0, Method::Head.as_ref());
     }
+    if !methods.contains(&Method::Options) {
+        allow.push(Method::Options.as_ref());
+    }
+    allow.join(", ")
 }
 
 impl From<matchit::Params<'_, '_>> for RouteParams {
     fn from(p: matchit::Params) -> Self {
-        let mut route_params = RouteParams(HashMap::new());
-        for (ident, value) in p.iter() {
-            route_params.0.insert(ident.into(), value.into());
+        let params: Vec<_> = p
+            .iter()
+            .map(|(ident, value)| (ident.into(), value.into()))
+            .collect();
+
+        RouteParams {
+            own: params.len(),
+            params,
         }
-
-        route_params
     }
 }
+
+#[test]
+fn allowed_methods_for_path() {
+    let router = Router::new()
+        .get("/users/:id", |_, _| Response::empty())
+        .delete("/users/:id", |_, _| Response::empty())
+        .post("/users", |_, _| Response::empty());
+
+    let allowed = router.allowed_methods("/users/7");
+    assert_eq!(allowed, vec![Method::Get, Method::Delete]);
+    assert_eq!(allow_header(&allowed), "HEAD, GET, DELETE, OPTIONS");
+    assert_eq!(
+        allow_header(&router.allowed_methods("/users")),
+        "POST, OPTIONS"
+    );
+    assert!(router.allowed_methods("/teams").is_empty());
+}
+
+#[test]
+fn inherited_params_follow_own_params() {
+    let pairs = |pairs: &[(&str, &str)]| {
+        pairs
+            .iter()
+            .map(|(k, v)| (k.to_string(), v.to_string()))
+            .collect::<Vec<_>>()
+    };
+
+    let mut params = RouteParams {
+        params: pairs(&[("team", "rust"), ("id", "7")]),
+        own: 2,
+    };
+    params.inherit(RouteParams {
+        params: pairs(&[("org", "acme"), ("id", "1")]),
+        own: 2,
+    });
+    assert_eq!(
+        params.as_slice(),
+        pairs(&[("team", "rust"), ("id", "7"), ("org", "acme")]).as_slice()
+    );
+    assert_eq!(
+        params.own(),
+        pairs(&[("team", "rust"), ("id", "7")]).as_slice()
+    );
+}
+
+#[test]
+fn mounted_route_path_extraction() {
+    use crate::extract::Path;
+    use serde::Deserialize;
+
+    #[derive(Debug, PartialEq, Deserialize)]
+    struct Member {
+        org: String,
+        team: String,
+        member: String,
+    }
+
+    let teams = || Router::new().get("/teams/:team/:member", |_, _| Response::empty());
+    let router = Router::new().mount("/mounted/:org", teams());
+
+    // resolve the request the way the mounted router's handler does
+    let (_, mut parent) = router.resolve(&Method::Get, "/mounted/acme/teams/rust/ferris");
+    let path = parent.remove(MOUNT_PATH_PARAM).unwrap_or_default();
+    let (_, mut params) = teams().resolve(&Method::Get, &format!("/{path}"));
+    params.inherit(parent);
+
+    assert_eq!(
+        Path::<(String, String)>::from_params(&params),
+        Ok(Path(("rust".to_string(), "ferris".to_string())))
+    );
+    assert_eq!(
+        Path::<Member>::from_params(&params),
+        Ok(Path(Member {
+            org: "acme".into(),
+            team: "rust".into(),
+            member: "ferris".into(),
+        }))
+    );
+    assert_eq!(
+        Path::<(String, String, String)>::from_params(&params).map_err(|e| e.status()),
+        Err(400)
+    );
+}
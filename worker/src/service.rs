@@ -0,0 +1,155 @@
+use std::{
+    mem::ManuallyDrop,
+    thread::{self, ThreadId},
+};
+
+use futures_util::future::poll_fn;
+use tower_service::Service;
+
+use crate::{Context, Env, Error, Result};
+
+/// Run a [`tower::Service`](tower_service::Service) handling `http` requests, such as an axum
+/// `Router`, as the fetch handler of a Worker. The `Env` and `Context` are inserted into the
+/// request's extensions, where the service can get them through [`WorkerExtensions`].
+///
+/// ```ignore
+/// #[event(fetch, http)]
+/// async fn main(req: HttpRequest, env: Env, ctx: Context) -> Result<HttpResponse<BoxBody>> {
+///     worker::serve(axum::Router::new().route("/", get(root)), req, env, ctx).await
+/// }
+///
+/// async fn root(req: axum::http::Request<axum::body::Body>) -> String {
+///     let env = req.extensions().env().unwrap();
+///     env.var("GREETING").unwrap().to_string()
+/// }
+/// ```
+pub async fn serve<S, ReqBody, ResBody>(
+    mut service: S,
+    mut req: http::Request<ReqBody>,
+    env: Env,
+    ctx: Context,
+) -> Result<http::Response<ResBody>>
+where
+    S: Service<http::Request<ReqBody>, Response = http::Response<ResBody>>,
+    S::Error: Into<Box<dyn std::error::Error + Send + Sync>>,
+{
+    req.extensions_mut()
+        .insert(Bindings(SendWrapper::new((env, ctx))));
+
+    poll_fn(|cx| service.poll_ready(cx))
+        .await
+        .map_err(|e| Error::Custom(e.into()))?;
+    service.call(req).await.map_err(|e| Error::Custom(e.into()))
+}
+
+/// Gets the bindings [`serve`] inserts into the extensions of the requests it passes on.
+pub trait WorkerExtensions {
+    /// The `Env` of the Worker, if the request came through [`serve`].
+    fn env(&self) -> Option<Env>;
+
+    /// The `Context` of the fetch event, if the request came through [`serve`].
+    fn context(&self) -> Option<Context>;
+}
+
+impl WorkerExtensions for http::Extensions {
+    fn env(&self) -> Option<Env> {
+        self.get::<Bindings>().map(|b| b.0.get().0.clone())
+    }
+
+    fn context(&self) -> Option<Context> {
+        self.get::<Bindings>().map(|b| b.0.get().1.clone())
+    }
+}
+
+/// The extension holding the bindings, which isn't exported so that they can only be reached
+/// through [`WorkerExtensions`].
+struct Bindings(SendWrapper<(Env, Context)>);
+
+/// Makes a value which isn't `Send` fit the `Send + Sync` bound of `http` extensions, by only
+/// giving access to it on the thread it was created on. Workers are single threaded, so this
+/// only fails if a service moves its requests elsewhere.
+struct SendWrapper<T> {
+    value: ManuallyDrop<T>,
+    thread: ThreadId,
+}
+
+// SAFETY: the value is only accessed or dropped on the thread which created it.
+unsafe impl<T> Send for SendWrapper<T> {}
+unsafe impl<T> Sync for SendWrapper<T> {}
+
+impl<T> SendWrapper<T> {
+    fn new(value: T) -> Self {
+        Self {
+            value: ManuallyDrop::new(value),
+            thread: thread::current().id(),
+        }
+    }
+
+    fn get(&self) -> &T {
+        assert!(
+            thread::current().id() == self.thread,
+            "Worker bindings can only be used on the thread which received the request"
+        );
+        &self.value
+    }
+}
+
+impl<T> Drop for SendWrapper<T> {
+    fn drop(&mut self) {
+        // the value is leaked rather than dropped on another thread
+        if thread::current().id() == self.thread {
+            // SAFETY: the value is never used again
+            unsafe { ManuallyDrop::drop(&mut self.value) }
+        }
+    }
+}
+
+#[test]
+fn serve_inserts_env_and_context() {
+    use futures_util::{future::Ready, FutureExt};
+    use std::{
+        convert::Infallible,
+        task::{Context as TaskContext, Poll},
+    };
+    use wasm_bindgen::{JsCast, JsValue};
+
+    struct HasBindings;
+
+    impl Service<http::Request<()>> for HasBindings {
+        type Response = http::Response<bool>;
+        type Error = Infallible;
+        type Future = Ready<std::result::Result<Self::Response, Self::Error>>;
+
+        fn poll_ready(
+            &mut self,
+            _: &mut TaskContext<'_>,
+        ) -> Poll<std::result::Result<(), Infallible>> {
+            Poll::Ready(Ok(()))
+        }
+
+        fn call(&mut self, req: http::Request<()>) -> Self::Future {
+            let found = req.extensions().get::<Bindings>().is_some();
+            futures_util::future::ready(Ok(http::Response::new(found)))
+        }
+    }
+
+    let req = http::Request::new(());
+    let env: Env = JsValue::NULL.unchecked_into();
+    let ctx = Context::new(JsValue::NULL.unchecked_into());
+
+    let resp = serve(HasBindings, req, env, ctx)
+        .now_or_never()
+        .unwrap()
+        .unwrap();
+    assert!(resp.into_body());
+}
+
+#[test]
+fn bindings_are_only_reachable_on_their_thread() {
+    let wrapper = std::sync::Arc::new(SendWrapper::new(1));
+    assert_eq!(*wrapper.get(), 1);
+
+    let other = wrapper.clone();
+    let result = thread::spawn(move || std::panic::catch_unwind(|| *other.get())).join();
+    assert!(result.unwrap().is_err());
+}
@@ -1,7 +1,7 @@
 use crate::{Error, Fetch, Method, Request, Result};
 use futures_channel::mpsc::UnboundedReceiver;
 use futures_util::Stream;
-use serde::Serialize;
+use serde::{de::DeserializeOwned, Serialize};
 use url::Url;
 
 use std::pin::Pin;
@@ -110,6 +110,24 @@ impl WebSocket {
         self.socket.send_with_u8_array(array).map_err(Error::from)
     }
 
+    /// Stores a value with this `WebSocket` which is kept while its Durable Object hibernates, e.g.
+    /// the ID of the user it belongs to. The serialized value is limited to 2,048 bytes.
+    pub fn serialize_attachment<T: Serialize>(&self, value: &T) -> Result<()> {
+        self.socket
+            .serialize_attachment(serde_wasm_bindgen::to_value(value)?)
+            .map_err(Error::from)
+    }
+
+    /// Retrieves the value stored with `serialize_attachment`, if there is one.
+    pub fn deserialize_attachment<T: DeserializeOwned>(&self) -> Result<Option<T>> {
+        let value = self.socket.deserialize_attachment()?;
+        if value.is_null() || value.is_undefined() {
+            Ok(None)
+        } else {
+            Ok(Some(serde_wasm_bindgen::from_value(value)?))
+        }
+    }
+
     /// Closes this channel.
     /// This method translates to three different underlying method calls based of the
     /// parameters passed.